@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use crate::{
+    dashboard::{MoonBuildDashboard, ToolChainLabel},
+    html::escape,
+};
+
+#[derive(Debug, thiserror::Error)]
+#[error("badge error")]
+pub struct BadgeError {
+    #[source]
+    kind: BadgeErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BadgeErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadgeState {
+    Passing,
+    Partial,
+    Failing,
+    Unknown,
+}
+
+impl BadgeState {
+    pub fn color(&self) -> &str {
+        match self {
+            BadgeState::Passing => "#4c1",
+            BadgeState::Partial => "#dfb317",
+            BadgeState::Failing => "#e05d44",
+            BadgeState::Unknown => "#9f9f9f",
+        }
+    }
+
+    fn from_counts(passed: usize, total: usize) -> BadgeState {
+        if total == 0 {
+            BadgeState::Unknown
+        } else if passed == total {
+            BadgeState::Passing
+        } else if passed == 0 {
+            BadgeState::Failing
+        } else {
+            BadgeState::Partial
+        }
+    }
+}
+
+/// Approximates the rendered width of `text` in Verdana 11px.
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+/// Renders a flat two-part badge in the style of shields.io.
+pub fn render_badge(label: &str, message: &str, color: &str) -> String {
+    let lw = text_width(label);
+    let mw = text_width(message);
+    let w = lw + mw;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{w}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{lw}" height="20" fill="#555"/><rect x="{lw}" width="{mw}" height="20" fill="{color}"/><rect width="{w}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{lx}" y="14">{label}</text><text x="{mx}" y="14">{message}</text></g></svg>
+"##,
+        w = w,
+        lw = lw,
+        mw = mw,
+        lx = lw / 2,
+        mx = lw + mw / 2,
+        label = escape(label),
+        message = escape(message),
+        color = color,
+    )
+}
+
+fn message(state: BadgeState, passed: usize, total: usize) -> String {
+    match state {
+        BadgeState::Passing => "passing".to_string(),
+        BadgeState::Partial => format!("{}/{} passing", passed, total),
+        BadgeState::Failing => "failing".to_string(),
+        BadgeState::Unknown => "unknown".to_string(),
+    }
+}
+
+/// Writes `<toolchain>/<source slug>.svg` for every source and
+/// `<toolchain>/ecosystem.svg` into `dir`.
+pub fn write_badges(run: &MoonBuildDashboard, dir: &Path) -> Result<usize, BadgeError> {
+    let mut count = 0;
+    for label in ToolChainLabel::all() {
+        let toolchain_dir = dir.join(label.to_string());
+        std::fs::create_dir_all(&toolchain_dir).map_err(|e| BadgeError {
+            kind: BadgeErrorKind::IOError(e),
+        })?;
+        let cells = run.cells(label);
+        let badge_label = format!("moonbit {}", label);
+
+        let mut passing: usize = 0;
+        for source in &run.sources {
+            let source_cells = cells
+                .iter()
+                .filter(|c| c.source.get_index() == source.get_index());
+            let (passed, total) = source_cells.fold((0, 0), |(p, t), c| {
+                (p + usize::from(c.result.status.is_success()), t + 1)
+            });
+            let state = BadgeState::from_counts(passed, total);
+            if state == BadgeState::Passing {
+                passing += 1;
+            }
+            let svg = render_badge(&badge_label, &message(state, passed, total), state.color());
+            std::fs::write(toolchain_dir.join(format!("{}.svg", source.slug())), svg).map_err(
+                |e| BadgeError {
+                    kind: BadgeErrorKind::IOError(e),
+                },
+            )?;
+            count += 1;
+        }
+
+        let total = run.sources.len();
+        let percent = (passing * 100).checked_div(total);
+        let color = match percent {
+            Some(90..) => BadgeState::Passing.color(),
+            Some(60..=89) => BadgeState::Partial.color(),
+            Some(_) => BadgeState::Failing.color(),
+            None => BadgeState::Unknown.color(),
+        };
+        let svg = render_badge(
+            &format!("ecosystem {}", label),
+            &format!("{}% ({}/{})", percent.unwrap_or(0), passing, total),
+            color,
+        );
+        std::fs::write(toolchain_dir.join("ecosystem.svg"), svg).map_err(|e| BadgeError {
+            kind: BadgeErrorKind::IOError(e),
+        })?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[test]
+fn badges_show_pass_partial_and_fail() {
+    assert_eq!(BadgeState::from_counts(9, 9), BadgeState::Passing);
+    assert_eq!(BadgeState::from_counts(4, 9), BadgeState::Partial);
+    assert_eq!(BadgeState::from_counts(0, 9), BadgeState::Failing);
+    assert_eq!(BadgeState::from_counts(0, 0), BadgeState::Unknown);
+
+    let svg = render_badge("moonbit <stable>", "passing", "#4c1");
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="181" height="20""#));
+    assert!(svg.contains(r##"<rect x="122" width="59" height="20" fill="#4c1"/>"##));
+    assert!(svg.contains("<title>moonbit &lt;stable&gt;: passing</title>"));
+}
+
+#[test]
+fn badges_cover_registries_and_unbuilt_sources() {
+    use crate::dashboard::{test_build, test_dashboard, MooncakeSource, Status};
+
+    // a/lib passes on the public registry and fails on acme, b/b is carried
+    // over with its checks passing, c/c was not built at all
+    let mut run = test_dashboard("2", &["a/lib", "a/lib", "b/b", "c/c"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[1] {
+        *registry = Some("acme".to_string());
+    }
+    let mut carried = test_build(2, "0.1.0", Status::Failure);
+    let cbt = carried.cbts[0].as_mut().unwrap();
+    for backends in [&mut cbt.check, &mut cbt.build, &mut cbt.test] {
+        for result in [&mut backends.wasm, &mut backends.wasm_gc, &mut backends.js] {
+            result.carried_over = Some("1".to_string());
+        }
+    }
+    for result in [
+        &mut cbt.check.wasm,
+        &mut cbt.check.wasm_gc,
+        &mut cbt.check.js,
+    ] {
+        result.status = Status::Success;
+    }
+    run.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Success),
+        test_build(1, "0.1.0", Status::Failure),
+        carried,
+    ];
+
+    let tmp = tempfile::tempdir().unwrap();
+    assert_eq!(write_badges(&run, tmp.path()).unwrap(), 10);
+    let read = |name: &str| std::fs::read_to_string(tmp.path().join("stable").join(name)).unwrap();
+    assert!(read("a-lib.svg").contains(">passing<"));
+    assert!(read("acme-a-lib.svg").contains(">failing<"));
+    assert!(read("b-b.svg").contains(">3/9 passing<"));
+    let unbuilt = read("c-c.svg");
+    assert!(unbuilt.contains(">unknown<") && unbuilt.contains(r##"fill="#9f9f9f""##));
+    let ecosystem = read("ecosystem.svg");
+    assert!(ecosystem.contains(">25% (1/4)<") && ecosystem.contains(r##"fill="#e05d44""##));
+
+    // nothing was built on bleeding
+    let bleeding =
+        |name: &str| std::fs::read_to_string(tmp.path().join("bleeding").join(name)).unwrap();
+    assert!(bleeding("a-lib.svg").contains(">unknown<"));
+    assert!(bleeding("ecosystem.svg").contains(">0% (0/4)<"));
+}
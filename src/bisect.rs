@@ -0,0 +1,95 @@
+#[derive(Debug, PartialEq, Eq)]
+pub enum BisectOutcome {
+    /// `first_bad` is the earliest item that fails, `last_good` the one before it.
+    Found { last_good: usize, first_bad: usize },
+    /// The oldest item already fails, so the culprit is outside the range.
+    FirstIsBad,
+    /// The newest item still passes, so nothing broke within the range.
+    LastIsGood,
+    /// Fewer than two items to search.
+    Empty,
+}
+
+/// Binary-searches `0..len`, ordered oldest to newest, for the first index
+/// where `is_good` turns false. Both ends are probed first, so a range that
+/// does not contain a transition is reported instead of silently accepted.
+pub fn bisect<E>(
+    len: usize,
+    mut is_good: impl FnMut(usize) -> Result<bool, E>,
+) -> Result<BisectOutcome, E> {
+    if len < 2 {
+        return Ok(BisectOutcome::Empty);
+    }
+    if !is_good(0)? {
+        return Ok(BisectOutcome::FirstIsBad);
+    }
+    if is_good(len - 1)? {
+        return Ok(BisectOutcome::LastIsGood);
+    }
+    let (mut good, mut bad) = (0, len - 1);
+    while bad - good > 1 {
+        let mid = good + (bad - good) / 2;
+        if is_good(mid)? {
+            good = mid;
+        } else {
+            bad = mid;
+        }
+    }
+    Ok(BisectOutcome::Found {
+        last_good: good,
+        first_bad: bad,
+    })
+}
+
+/// Numeric components of the first version in a `moon version` or `moonc -v`
+/// output, e.g. `[0, 1, 20241202]` for `v0.1.20241202+0a5e8e4a1`, for ordering toolchains.
+pub fn version_key(output: &str) -> Vec<u64> {
+    let Some(version) = output
+        .split_whitespace()
+        .map(|w| w.trim_start_matches('v'))
+        .find(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+    else {
+        return vec![];
+    };
+    version
+        .split(['+', '-'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+#[test]
+fn bisect_finds_first_bad() {
+    for culprit in 1..10 {
+        let mut probes = 0;
+        let outcome = bisect::<()>(10, |i| {
+            probes += 1;
+            Ok(i < culprit)
+        });
+        assert_eq!(
+            outcome,
+            Ok(BisectOutcome::Found {
+                last_good: culprit - 1,
+                first_bad: culprit
+            })
+        );
+        assert!(probes <= 6);
+    }
+    assert_eq!(bisect::<()>(3, |_| Ok(true)), Ok(BisectOutcome::LastIsGood));
+    assert_eq!(
+        bisect::<()>(3, |_| Ok(false)),
+        Ok(BisectOutcome::FirstIsBad)
+    );
+}
+
+#[test]
+fn toolchain_versions_order_numerically() {
+    assert_eq!(version_key("v0.1.20241202+0a5e8e4a1"), vec![0, 1, 20241202]);
+    assert_eq!(
+        version_key("moon 0.1.20241202 (c4a5e72 2024-12-02)"),
+        vec![0, 1, 20241202]
+    );
+    assert!(version_key("v0.1.9") < version_key("v0.1.10"));
+}
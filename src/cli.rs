@@ -9,6 +9,16 @@ pub struct MoonBuildDashBoardCli {
 #[derive(Debug, clap::Parser)]
 pub enum MoonBuildDashBoardSubcommands {
     Stat(StatSubcommand),
+    Report(ReportSubcommand),
+    Metrics(MetricsSubcommand),
+    Badges(BadgesSubcommand),
+    Diff(DiffSubcommand),
+    Perf(PerfSubcommand),
+    History(HistorySubcommand),
+    BisectToolchain(BisectToolchainSubcommand),
+    BisectSource(BisectSourceSubcommand),
+    Health(HealthSubcommand),
+    GenList(GenListSubcommand),
 }
 
 #[derive(Debug, clap::Parser)]
@@ -21,4 +31,229 @@ pub struct StatSubcommand {
     pub skip_install: bool,
     #[clap(long)]
     pub skip_update: bool,
+    /// Rerun a failed cell up to this many times, marking it flaky if it then passes
+    #[clap(long, default_value_t = 0)]
+    pub retries: u32,
+    /// Package weighted up in the health score, may be repeated; defaults to `moonbitlang/core` and `moonbitlang/x`
+    #[clap(long = "flagship")]
+    pub flagships: Vec<String>,
+    /// Registry include/exclude rules, defaults to `registry-rules.json` when it exists
+    #[clap(long)]
+    pub rules: Option<PathBuf>,
+    /// How dependency versions are chosen: as-published, latest or minimal
+    #[clap(long, default_value = "as-published")]
+    pub resolution: crate::dashboard::ResolutionMode,
+    /// Also build the upstream repository of registry sources at the head of its default branch
+    #[clap(long)]
+    pub upstream: bool,
+    /// Registries besides mooncakes.io, defaults to `registries.json` when it exists
+    #[clap(long)]
+    pub registries: Option<PathBuf>,
+    /// Reuse the recorded results of source revisions already built with the same toolchain
+    #[clap(long)]
+    pub incremental: bool,
+    /// Run every build in a bubblewrap sandbox with a read-only toolchain and only the workdir writable
+    #[clap(long)]
+    pub sandbox: bool,
+    /// Deny network access inside the sandbox
+    #[clap(long, requires = "sandbox")]
+    pub deny_network: bool,
+    /// Address space limit of every `moon` invocation, in MiB
+    #[clap(long, value_parser = parse_mib)]
+    pub limit_memory: Option<u64>,
+    /// CPU time limit of each process of a `moon` invocation, in seconds; every `moonc` it starts gets a fresh budget
+    #[clap(long)]
+    pub limit_cpu: Option<u64>,
+    /// Largest file a `moon` invocation may write, in MiB
+    #[clap(long, value_parser = parse_mib)]
+    pub limit_file_size: Option<u64>,
+    /// Process limit of every `moon` invocation, counting all processes of the user
+    #[clap(long)]
+    pub limit_processes: Option<u64>,
+}
+
+/// Parses a size in MiB into bytes.
+fn parse_mib(s: &str) -> Result<u64, String> {
+    let mib: u64 = s
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    mib.checked_mul(1024 * 1024)
+        .ok_or_else(|| format!("{} MiB is too large", mib))
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ReportSubcommand {
+    #[clap(long, value_enum, default_value = "markdown")]
+    pub format: ReportFormat,
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Run to report on, by run id or run number, defaults to the latest run
+    #[clap(long)]
+    pub run_id: Option<String>,
+    /// Number of slowest cells to list
+    #[clap(long, default_value_t = 10)]
+    pub slowest: usize,
+    /// Number of previous runs listed in the HTML history table
+    #[clap(long, default_value_t = 30)]
+    pub history: usize,
+    /// Path prefix the HTML report links published files under
+    #[clap(long, default_value = "/")]
+    pub base_path: String,
+    /// Write the report to this file instead of stdout
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+    /// Also append the report to `$GITHUB_STEP_SUMMARY` when it is set
+    #[clap(long)]
+    pub github_step_summary: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct MetricsSubcommand {
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Write the metrics of the latest run to this file, e.g. a node_exporter textfile `.prom`
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+    /// Serve `/metrics` on this address instead, e.g. `0.0.0.0:9184`
+    #[clap(long, conflicts_with = "output")]
+    pub listen: Option<String>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct BadgesSubcommand {
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Directory the badges are written to
+    #[clap(long, default_value = "badges")]
+    pub output_dir: PathBuf,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct DiffSubcommand {
+    /// Base run, by run id or run number
+    pub from: String,
+    /// Run to compare against the base, by run id or run number
+    pub to: String,
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Print the diff as JSON instead of a table
+    #[clap(long)]
+    pub json: bool,
+    /// Also list cells that pass in both runs
+    #[clap(long)]
+    pub all: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct PerfSubcommand {
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Number of previous runs the baseline median is computed from
+    #[clap(long, default_value_t = 10)]
+    pub window: usize,
+    /// Relative change from the baseline to flag, in percent
+    #[clap(long, default_value_t = 20.0)]
+    pub threshold: f64,
+    /// Changes smaller than this many milliseconds are treated as noise
+    #[clap(long, default_value_t = 200)]
+    pub min_delta_ms: u64,
+    /// Print the changes as JSON instead of a report
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct HistorySubcommand {
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Only show sources whose name contains this string
+    #[clap(long)]
+    pub source: Option<String>,
+    /// Print the failure streaks as JSON instead of a report
+    #[clap(long)]
+    pub json: bool,
+    /// Write the failure streaks as JSON to this file
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct BisectToolchainSubcommand {
+    /// Directory of installed toolchains, one `~/.moon`-like subdirectory each, ordered by their `moonc` and `moon` versions
+    pub toolchains: PathBuf,
+    /// Source to build, in the `repos.txt` syntax; only its first revision or version is used
+    #[clap(long)]
+    pub source: String,
+    /// Cell to bisect on, e.g. `build:js`; defaults to the whole matrix
+    #[clap(long)]
+    pub cell: Option<crate::dashboard::MoonCommand>,
+    /// Registries besides mooncakes.io, defaults to `registries.json` when it exists
+    #[clap(long)]
+    pub registries: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct BisectSourceSubcommand {
+    /// Git repository of the source
+    pub url: String,
+    /// Revision the cell passes at
+    pub good_rev: String,
+    /// Revision the cell fails at
+    pub bad_rev: String,
+    /// Cell to bisect on, e.g. `build:js`
+    #[clap(long)]
+    pub cell: crate::dashboard::MoonCommand,
+    /// Toolchain directory laid out like `~/.moon` to build with instead of the one on `PATH`
+    #[clap(long)]
+    pub toolchain: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct HealthSubcommand {
+    /// History file to read runs from, defaults to `data/data.jsonl`
+    #[clap(long)]
+    pub data: Option<PathBuf>,
+    /// Number of most recent runs to show
+    #[clap(long, default_value_t = 30)]
+    pub last: usize,
+    /// Print the health of each run as JSON instead of a table
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct GenListSubcommand {
+    /// Source list to update; only its generated part is rewritten
+    #[clap(default_value = "repos.txt")]
+    pub file: PathBuf,
+    /// Only list packages of this owner, may be repeated
+    #[clap(long = "owner")]
+    pub owners: Vec<String>,
+    /// Only list packages with this keyword, may be repeated
+    #[clap(long = "keyword")]
+    pub keywords: Vec<String>,
+    /// Only list packages whose latest version was published on or after this date, e.g. `2024-09-01`
+    #[clap(long)]
+    pub updated_since: Option<String>,
+    /// Number of latest versions to list for each package
+    #[clap(long, default_value_t = 1)]
+    pub versions: usize,
+    /// Registry include/exclude rules, defaults to `registry-rules.json` when it exists
+    #[clap(long)]
+    pub rules: Option<PathBuf>,
+    /// Print the summary without writing the file
+    #[clap(long)]
+    pub dry_run: bool,
 }
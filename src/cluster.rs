@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    dashboard::{MoonBuildDashboard, Status, ToolChainLabel},
+    deps::{blocked_failures, blocked_keys},
+};
+
+/// At most this many diagnostics are kept per cell.
+pub const MAX_DIAGNOSTICS: usize = 5;
+const MAX_DIAGNOSTIC_LEN: usize = 300;
+
+/// Signature of failing cells that did not leave any diagnostics behind.
+pub const NO_DIAGNOSTICS: &str = "no compiler errors captured";
+
+/// Words whose next word names something defined by the package.
+const NAMING_WORDS: &[&str] = &[
+    "identifier",
+    "type",
+    "field",
+    "method",
+    "constructor",
+    "package",
+    "function",
+    "trait",
+    "variable",
+    "label",
+    "module",
+    "value",
+];
+
+fn truncate(s: &str) -> String {
+    match s.char_indices().nth(MAX_DIAGNOSTIC_LEN) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+/// Picks the compiler errors out of captured `moon` output as `[code] message`.
+/// Falls back to `error:` lines from `moon` itself when the compiler reported nothing.
+pub fn extract_diagnostics(output: &str) -> Vec<String> {
+    let mut diagnostics = vec![];
+    let mut fallback = vec![];
+    let mut pending: Option<&str> = None;
+    for line in output.lines() {
+        let line = line.trim();
+        // Error: [4021]
+        //    ╭─[main.mbt:2:3]
+        //  2 │   foo()
+        //    │    ╰─── The value identifier foo is unbound.
+        if let Some(code) = line
+            .strip_prefix("Error: [")
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            pending = Some(code);
+        } else if let (Some(code), Some((_, message))) = (pending, line.split_once('╰')) {
+            let message = message.trim_start_matches(['─', '┬', ' ']);
+            diagnostics.push(truncate(&format!("[{}] {}", code, message)));
+            pending = None;
+        // main.mbt:2:3-2:6 [E4021] The value identifier foo is unbound.
+        } else if let Some((_, rest)) = line.split_once(" [E") {
+            if let Some((code, message)) = rest.split_once("] ") {
+                if !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()) {
+                    diagnostics.push(truncate(&format!("[{}] {}", code, message)));
+                }
+            }
+        } else if line.to_lowercase().starts_with("error:") {
+            fallback.push(truncate(line));
+        }
+    }
+    if diagnostics.is_empty() {
+        diagnostics = fallback;
+    }
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+    diagnostics
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || "_@/\\.:$~-".contains(c)
+}
+
+/// Turns a diagnostic into a message template by replacing paths, numbers,
+/// quoted text and identifiers with placeholders, keeping the error code.
+pub fn signature(diagnostic: &str) -> String {
+    let mut words = vec![];
+    let mut previous = String::new();
+    let mut after_colon = false;
+    let depth = |s: &str| s.matches('[').count() as isize - s.matches(']').count() as isize;
+    // a type such as `Map[String, Int]` is one token, even when it spans words
+    let mut tokens: Vec<String> = vec![];
+    for word in diagnostic.split_whitespace() {
+        match tokens.last_mut() {
+            Some(last) if depth(last) > 0 => {
+                last.push(' ');
+                last.push_str(word);
+            }
+            _ => tokens.push(word.to_string()),
+        }
+    }
+    for (i, token) in tokens.iter().enumerate() {
+        if i == 0 && token.starts_with('[') && token.ends_with(']') {
+            words.push(token.to_string());
+            continue;
+        }
+        let start = token.find(is_word_char).unwrap_or(token.len());
+        let (prefix, rest) = token.split_at(start);
+        let mut end = rest
+            .trim_end_matches(|c: char| !is_word_char(c) || ".:,;".contains(c))
+            .len();
+        // keep the closing brackets of type arguments with the type
+        while depth(&rest[..end]) > 0 && rest[end..].starts_with(']') {
+            end += 1;
+        }
+        let (core, suffix) = rest.split_at(end);
+
+        let quoted = prefix.ends_with(['`', '\'', '"']);
+        let replaced = if core.is_empty() {
+            core.to_string()
+        } else if core.contains(['/', '\\']) || core.ends_with(".mbt") || core.ends_with(".json") {
+            "<path>".to_string()
+        } else if core.chars().any(|c| c.is_ascii_digit()) {
+            "<n>".to_string()
+        } else if quoted
+            || NAMING_WORDS.contains(&previous.as_str())
+            || after_colon
+            || core.contains(['_', '@', '.', ':', '$', '[', '('])
+            || core.chars().skip(1).any(|c| c.is_uppercase())
+        {
+            "_".to_string()
+        } else {
+            core.to_string()
+        };
+        words.push(format!("{}{}{}", prefix, replaced, suffix));
+        previous = core.to_lowercase();
+        after_colon = suffix.ends_with(':');
+    }
+    words.join(" ")
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cluster {
+    pub toolchain: ToolChainLabel,
+    pub signature: String,
+    /// `name version` of every affected source.
+    pub sources: Vec<String>,
+    /// Number of failing cells with this signature.
+    pub cells: usize,
+    /// One diagnostic as it was reported, with the source it came from.
+    pub example: Option<(String, String)>,
+}
+
+/// Groups the failing cells of a run by the signature of their first diagnostic,
+/// largest clusters first. Cells blocked by a failing tracked dependency are left out.
+pub fn cluster_failures(run: &MoonBuildDashboard) -> Vec<Cluster> {
+    let blocked = blocked_failures(run);
+    let blocked = blocked_keys(&blocked);
+    let mut clusters: BTreeMap<(ToolChainLabel, String), Cluster> = BTreeMap::new();
+    for label in ToolChainLabel::all() {
+        for cell in run.cells(label) {
+            let name = cell.source.full_name();
+            if cell.result.status != Status::Failure
+                || blocked.contains(&(label, name.as_str(), cell.version, cell.cmd))
+            {
+                continue;
+            }
+            let first = cell.result.diagnostics.first();
+            let signature = first
+                .map(|d| signature(d))
+                .unwrap_or_else(|| NO_DIAGNOSTICS.to_string());
+            let source = format!("{} {}", name, cell.version);
+            let cluster = clusters
+                .entry((label, signature.clone()))
+                .or_insert_with(|| Cluster {
+                    toolchain: label,
+                    signature,
+                    sources: vec![],
+                    cells: 0,
+                    example: None,
+                });
+            cluster.cells += 1;
+            if !cluster.sources.contains(&source) {
+                cluster.sources.push(source.clone());
+            }
+            if cluster.example.is_none() {
+                cluster.example = first.map(|d| (source, d.clone()));
+            }
+        }
+    }
+    let mut clusters: Vec<Cluster> = clusters.into_values().collect();
+    clusters.sort_by(|a, b| {
+        a.toolchain
+            .cmp(&b.toolchain)
+            .then(b.sources.len().cmp(&a.sources.len()))
+            .then(b.cells.cmp(&a.cells))
+    });
+    clusters
+}
+
+#[test]
+fn diagnostics_cluster_by_signature() {
+    let ariadne = "Error: [4021]\n   ╭─[/tmp/x/src/main.mbt:2:3]\n 2 │   foo()\n   │   ─┬─\n   │    ╰─── The value identifier foo is unbound.\n───╯\nerror: failed when checking project";
+    let legacy = "/home/u/y/lib/a.mbt:10:1-10:4 [E4021] The value identifier bar_baz is unbound.";
+    let a = extract_diagnostics(ariadne);
+    let b = extract_diagnostics(legacy);
+    assert_eq!(a, vec!["[4021] The value identifier foo is unbound."]);
+    assert_eq!(b, vec!["[4021] The value identifier bar_baz is unbound."]);
+    assert_eq!(signature(&a[0]), signature(&b[0]));
+    assert_eq!(
+        signature("[4014] Expr Type: Array[Int], expected `@json.T` in /a/b.mbt"),
+        "[4014] Expr Type: _, expected `_` in <path>"
+    );
+    assert_eq!(
+        signature("[4014] Expr Type: Map[String, Array[Int]], expected Int"),
+        "[4014] Expr Type: _, expected Int"
+    );
+    assert_ne!(
+        signature(&a[0]),
+        signature("[4015] Type Int has no method to_json.")
+    );
+    assert_eq!(
+        extract_diagnostics("error: failed to download moonbitlang/x"),
+        vec!["error: failed to download moonbitlang/x"]
+    );
+}
+
+#[test]
+fn failures_cluster_across_sources() {
+    use crate::dashboard::{test_build, test_dashboard, ModuleInfo};
+
+    // a/a and b/b fail check:js with the same unbound identifier error under
+    // other names, c/c fails build:js without a diagnostic, d/d is only flaky
+    // and e/e fails check:js because its dependency b/b does
+    let mut run = test_dashboard("1", &["a/a", "b/b", "c/c", "d/d", "e/e"]);
+    let failing = |source: usize, diagnostic: Option<&str>| {
+        let mut state = test_build(source, "0.1.0", Status::Success);
+        let cbt = state.cbts[0].as_mut().unwrap();
+        let result = if diagnostic.is_some() {
+            &mut cbt.check.js
+        } else {
+            &mut cbt.build.js
+        };
+        result.status = Status::Failure;
+        result.diagnostics = diagnostic.map(|d| d.to_string()).into_iter().collect();
+        state
+    };
+    let mut flaky = test_build(3, "0.1.0", Status::Success);
+    flaky.cbts[0].as_mut().unwrap().check.js.status = Status::Flaky;
+    let mut blocked = failing(4, Some("[4021] The value identifier e is unbound."));
+    blocked.modules = vec![Some(ModuleInfo {
+        name: "e/e".to_string(),
+        deps: [("b/b".to_string(), "0.1.0".to_string())].into(),
+    })];
+    run.stable_release_data = vec![
+        failing(0, Some("[4021] The value identifier foo is unbound.")),
+        failing(1, Some("[4021] The value identifier bar_baz is unbound.")),
+        failing(2, None),
+        flaky,
+        blocked,
+    ];
+
+    let clusters = cluster_failures(&run);
+    assert_eq!(clusters.len(), 2);
+    assert_eq!(
+        clusters[0].signature,
+        signature("[4021] The value identifier foo is unbound.")
+    );
+    assert_eq!(clusters[0].sources, vec!["a/a 0.1.0", "b/b 0.1.0"]);
+    assert_eq!(clusters[0].cells, 2);
+    let (source, diagnostic) = clusters[0].example.as_ref().unwrap();
+    assert_eq!(source, "a/a 0.1.0");
+    assert!(diagnostic.contains("foo"));
+    assert_eq!(clusters[1].signature, NO_DIAGNOSTICS);
+    assert_eq!(clusters[1].sources, vec!["c/c 0.1.0"]);
+    assert!(clusters[1].example.is_none());
+}
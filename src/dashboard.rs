@@ -1,3 +1,5 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
@@ -6,6 +8,12 @@ pub enum MooncakeSource {
         name: String,
         version: Vec<String>,
         index: usize,
+        /// Versions that were yanked from the registry when they were built.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        yanked: Vec<String>,
+        /// Registry the package comes from, the public mooncakes.io registry when absent.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        registry: Option<String>,
     },
     Git {
         url: String,
@@ -21,9 +29,81 @@ impl MooncakeSource {
             MooncakeSource::Git { index, .. } => *index,
         }
     }
+
+    pub fn set_index(&mut self, i: usize) {
+        match self {
+            MooncakeSource::MooncakesIO { index, .. } => *index = i,
+            MooncakeSource::Git { index, .. } => *index = i,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            MooncakeSource::MooncakesIO { name, .. } => name,
+            MooncakeSource::Git { url, .. } => url,
+        }
+    }
+
+    /// Registry of a registry source, `None` for the public one and git sources.
+    pub fn registry(&self) -> Option<&str> {
+        match self {
+            MooncakeSource::MooncakesIO { registry, .. } => registry.as_deref(),
+            MooncakeSource::Git { .. } => None,
+        }
+    }
+
+    /// `name` prefixed with the registry of a private registry source, as in
+    /// `repos.txt`, e.g. `acme:a/lib`, telling apart packages of the same name.
+    pub fn full_name(&self) -> String {
+        match self.registry() {
+            Some(registry) => format!("{}:{}", registry, self.name()),
+            None => self.name().to_string(),
+        }
+    }
+
+    /// A file-name friendly identifier that does not depend on `index`,
+    /// e.g. `moonbitlang-x`, `acme-a-lib` or `github.com-moonbitlang-core`.
+    pub fn slug(&self) -> String {
+        let raw = match self {
+            MooncakeSource::MooncakesIO {
+                name,
+                registry: Some(registry),
+                ..
+            } => format!("{}/{}", registry, name),
+            MooncakeSource::MooncakesIO { name, .. } => name.clone(),
+            MooncakeSource::Git { url, .. } => url
+                .split_once("://")
+                .map_or(url.as_str(), |(_, rest)| rest)
+                .trim_end_matches(".git")
+                .to_string(),
+        };
+        let mut slug = String::new();
+        for c in raw.chars() {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        slug.trim_end_matches('-').to_string()
+    }
+
+    pub fn is_yanked(&self, v: &str) -> bool {
+        match self {
+            MooncakeSource::MooncakesIO { yanked, .. } => yanked.iter().any(|y| y == v),
+            MooncakeSource::Git { .. } => false,
+        }
+    }
+
+    pub fn versions(&self) -> &[String] {
+        match self {
+            MooncakeSource::MooncakesIO { version, .. } => version,
+            MooncakeSource::Git { rev, .. } => rev,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
 pub enum Backend {
     Wasm,
     WasmGC,
@@ -38,9 +118,13 @@ impl Backend {
             Backend::Js => "js",
         }
     }
+
+    pub fn all() -> [Backend; 3] {
+        [Backend::Wasm, Backend::WasmGC, Backend::Js]
+    }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
 pub enum MoonCommand {
     Check(Backend),
     Build(Backend),
@@ -57,22 +141,140 @@ impl MoonCommand {
             }
         }
     }
+
+    pub fn name(&self) -> &str {
+        match self {
+            MoonCommand::Check(_) => "check",
+            MoonCommand::Build(_) => "build",
+            MoonCommand::Test(_) => "test",
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            MoonCommand::Check(backend)
+            | MoonCommand::Build(backend)
+            | MoonCommand::Test(backend) => *backend,
+        }
+    }
+
+    /// All cells of the matrix, in the order `run_matrix` executes them.
+    pub fn all() -> Vec<MoonCommand> {
+        let mut cmds = vec![];
+        for backend in Backend::all() {
+            cmds.push(MoonCommand::Check(backend));
+        }
+        for backend in Backend::all() {
+            cmds.push(MoonCommand::Build(backend));
+        }
+        for backend in Backend::all() {
+            cmds.push(MoonCommand::Test(backend));
+        }
+        cmds
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl fmt::Display for MoonCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name(), self.backend().to_flag())
+    }
+}
+
+impl FromStr for MoonCommand {
+    type Err = String;
+
+    /// Parses a cell such as `build:js` or `check:wasm-gc`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (cmd, backend) = s
+            .split_once(':')
+            .ok_or_else(|| format!("invalid cell `{}`, expected <command>:<backend>", s))?;
+        let backend = match backend {
+            "wasm" => Backend::Wasm,
+            "wasm-gc" | "wasm_gc" => Backend::WasmGC,
+            "js" => Backend::Js,
+            _ => return Err(format!("unknown backend `{}`", backend)),
+        };
+        match cmd {
+            "check" => Ok(MoonCommand::Check(backend)),
+            "build" => Ok(MoonCommand::Build(backend)),
+            "test" => Ok(MoonCommand::Test(backend)),
+            _ => Err(format!("unknown command `{}`", cmd)),
+        }
+    }
+}
+
+/// How the dependency versions in a source's `moon.mod.json` are chosen before building.
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, Hash, Eq, PartialEq, PartialOrd, Ord,
+)]
+pub enum ResolutionMode {
+    /// As published by the package author.
+    #[default]
+    AsPublished,
+    /// Every registry dependency bumped to its latest version.
+    Latest,
+    /// Every dependency pinned at the lowest version its requirement allows,
+    /// which is what moon's minimal version selection picks when nothing else
+    /// in the build asks for a newer one. Requirements without an inclusive
+    /// lower bound are left alone.
+    Minimal,
+}
+
+impl fmt::Display for ResolutionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolutionMode::AsPublished => write!(f, "as-published"),
+            ResolutionMode::Latest => write!(f, "latest"),
+            ResolutionMode::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
+impl FromStr for ResolutionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "as-published" => Ok(ResolutionMode::AsPublished),
+            "latest" => Ok(ResolutionMode::Latest),
+            "minimal" => Ok(ResolutionMode::Minimal),
+            _ => Err(format!(
+                "unknown resolution mode `{}`, expected as-published, latest or minimal",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub enum ToolChainLabel {
     Stable,
     Bleeding,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl ToolChainLabel {
+    pub fn all() -> [ToolChainLabel; 2] {
+        [ToolChainLabel::Stable, ToolChainLabel::Bleeding]
+    }
+}
+
+impl fmt::Display for ToolChainLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolChainLabel::Stable => write!(f, "stable"),
+            ToolChainLabel::Bleeding => write!(f, "bleeding"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolChainVersion {
     pub label: ToolChainLabel,
     pub moon_version: String,
     pub moonc_version: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoonBuildDashboard {
     pub run_id: String,
     pub run_number: String,
@@ -85,37 +287,490 @@ pub struct MoonBuildDashboard {
 
     pub bleeding_toolchain_version: ToolChainVersion,
     pub bleeding_release_data: Vec<BuildState>,
+
+    /// Stable vs bleeding classification of every cell, absent in older runs.
+    #[serde(default)]
+    pub toolchain_comparison: Vec<ComparisonState>,
+
+    /// Flakiness of each source over recent runs, absent in older runs.
+    #[serde(default)]
+    pub flakiness: Vec<SourceFlakiness>,
+
+    /// Aggregate health of each toolchain, absent in older runs.
+    #[serde(default)]
+    pub health: Vec<HealthScore>,
+}
+
+impl MoonBuildDashboard {
+    pub fn toolchain_version(&self, label: ToolChainLabel) -> &ToolChainVersion {
+        match label {
+            ToolChainLabel::Stable => &self.stable_toolchain_version,
+            ToolChainLabel::Bleeding => &self.bleeding_toolchain_version,
+        }
+    }
+
+    pub fn release_data(&self, label: ToolChainLabel) -> &[BuildState] {
+        match label {
+            ToolChainLabel::Stable => &self.stable_release_data,
+            ToolChainLabel::Bleeding => &self.bleeding_release_data,
+        }
+    }
+
+    /// Looks up the result of `cmd` for the `version_index`-th version of
+    /// `source` on the given toolchain.
+    pub fn cell(
+        &self,
+        label: ToolChainLabel,
+        source: usize,
+        version_index: usize,
+        cmd: MoonCommand,
+    ) -> Option<&ExecuteResult> {
+        self.release_data(label)
+            .iter()
+            .find(|state| state.source == source)
+            .and_then(|state| state.cbts.get(version_index))
+            .and_then(|cbt| cbt.as_ref())
+            .map(|cbt| cbt.get(cmd))
+    }
+
+    /// Flattens the results of one toolchain into individual matrix cells.
+    /// Versions that produced no data (failed clone, download, ...) are skipped.
+    pub fn cells(&self, label: ToolChainLabel) -> Vec<Cell<'_>> {
+        let mut cells = vec![];
+        for state in self.release_data(label) {
+            let Some(source) = self.sources.get(state.source) else {
+                continue;
+            };
+            for (version_index, cbt) in state.cbts.iter().enumerate() {
+                let Some(cbt) = cbt else {
+                    continue;
+                };
+                let version = source
+                    .versions()
+                    .get(version_index)
+                    .map(|v| v.as_str())
+                    .unwrap_or("");
+                let revision = state
+                    .revisions
+                    .get(version_index)
+                    .and_then(|r| r.as_deref());
+                let module = state.modules.get(version_index).and_then(|m| m.as_ref());
+                for cmd in MoonCommand::all() {
+                    cells.push(Cell {
+                        label,
+                        source,
+                        version,
+                        version_index,
+                        revision,
+                        module,
+                        cmd,
+                        result: cbt.get(cmd),
+                    });
+                }
+            }
+        }
+        cells
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy)]
+pub struct Cell<'a> {
+    pub label: ToolChainLabel,
+    pub source: &'a MooncakeSource,
+    pub version: &'a str,
+    pub version_index: usize,
+    pub revision: Option<&'a str>,
+    pub module: Option<&'a ModuleInfo>,
+    pub cmd: MoonCommand,
+    pub result: &'a ExecuteResult,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
 pub enum Status {
     Success,
     Failure,
+    /// Failed at first, then passed on a rerun.
+    Flaky,
+    /// Failed by touching what the build sandbox denies, such as the network or files outside the workdir.
+    SandboxViolation,
+    /// Stopped by one of the resource limits of a cell.
+    ResourceExceeded {
+        kind: ResourceKind,
+    },
+}
+
+/// A resource limited for every `moon` invocation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Hash, Eq, PartialEq)]
+pub enum ResourceKind {
+    AddressSpace,
+    CpuTime,
+    FileSize,
+    Processes,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceKind::AddressSpace => write!(f, "address space"),
+            ResourceKind::CpuTime => write!(f, "CPU time"),
+            ResourceKind::FileSize => write!(f, "file size"),
+            ResourceKind::Processes => write!(f, "process count"),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Status {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Status::Success | Status::Flaky)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attempt {
+    pub status: Status,
+    pub start_time: String,
+    pub elapsed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecuteResult {
     pub status: Status,
     pub start_time: String,
     pub elapsed: u64,
+    /// Every attempt, when the cell was rerun after a failure.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<Attempt>,
+    /// Compiler errors captured from a failing cell.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<String>,
+    /// Run the result was carried over from by an incremental run, instead of being rebuilt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carried_over: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackendState {
     pub wasm: ExecuteResult,
     pub wasm_gc: ExecuteResult,
     pub js: ExecuteResult,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl BackendState {
+    pub fn get(&self, backend: Backend) -> &ExecuteResult {
+        match backend {
+            Backend::Wasm => &self.wasm,
+            Backend::WasmGC => &self.wasm_gc,
+            Backend::Js => &self.js,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CBT {
     pub check: BackendState,
     pub build: BackendState,
     pub test: BackendState,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl CBT {
+    pub fn get(&self, cmd: MoonCommand) -> &ExecuteResult {
+        match cmd {
+            MoonCommand::Check(backend) => self.check.get(backend),
+            MoonCommand::Build(backend) => self.build.get(backend),
+            MoonCommand::Test(backend) => self.test.get(backend),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildState {
     pub source: usize,
     pub cbts: Vec<Option<CBT>>,
+    /// Revision actually built for each version: the commit hash for git
+    /// sources, the version for registry sources. Absent in older runs.
+    #[serde(default)]
+    pub revisions: Vec<Option<String>>,
+    /// Module declared by `moon.mod.json` for each version. Absent in older runs.
+    #[serde(default)]
+    pub modules: Vec<Option<ModuleInfo>>,
+    /// How dependency versions were chosen for each version, `AsPublished` where
+    /// another mode was asked for but could not be applied. Absent in older runs.
+    #[serde(default)]
+    pub resolutions: Vec<ResolutionMode>,
+    /// The upstream repository of a registry source built at its default branch, when compared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<UpstreamBuild>,
+    /// Registry versions not built because their package failed verification, by version.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub integrity: BTreeMap<String, IntegrityViolation>,
+}
+
+/// Why a downloaded registry package is broken, which is the package's fault and not the toolchain's.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum IntegrityViolation {
+    /// The archive has no `moon.mod.json` at its root.
+    MissingModule,
+    /// `moon.mod.json` cannot be parsed.
+    InvalidModule(String),
+    NameMismatch {
+        expected: String,
+        found: String,
+    },
+    VersionMismatch {
+        expected: String,
+        found: String,
+    },
+    /// An archive entry or symlink resolving outside the destination directory.
+    PathEscape(String),
+    /// An archive entry that is a symbolic link.
+    Symlink(String),
+    /// The archive is larger than the limit, so its download was stopped.
+    ArchiveTooLarge {
+        limit: u64,
+    },
+    /// The unpacked content of the archive is larger than the limit.
+    TooLarge {
+        size: u64,
+        limit: u64,
+    },
+}
+
+impl fmt::Display for IntegrityViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityViolation::MissingModule => write!(f, "no moon.mod.json"),
+            IntegrityViolation::InvalidModule(e) => write!(f, "invalid moon.mod.json: {}", e),
+            IntegrityViolation::NameMismatch { expected, found } => {
+                write!(f, "moon.mod.json names {} instead of {}", found, expected)
+            }
+            IntegrityViolation::VersionMismatch { expected, found } => {
+                write!(
+                    f,
+                    "moon.mod.json has version {} instead of {}",
+                    found, expected
+                )
+            }
+            IntegrityViolation::PathEscape(path) => {
+                write!(f, "{} escapes the package directory", path)
+            }
+            IntegrityViolation::Symlink(path) => write!(f, "{} is a symbolic link", path),
+            IntegrityViolation::ArchiveTooLarge { limit } => {
+                write!(f, "archive exceeds the limit of {} bytes", limit)
+            }
+            IntegrityViolation::TooLarge { size, limit } => {
+                write!(f, "{} bytes exceed the limit of {} bytes", size, limit)
+            }
+        }
+    }
+}
+
+/// A registry source's upstream repository, built at the head of its default branch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamBuild {
+    pub repository: String,
+    /// Short hash of the commit built.
+    pub revision: Option<String>,
+    pub cbt: Option<CBT>,
+}
+
+/// Name and dependencies from a source's `moon.mod.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModuleInfo {
+    pub name: String,
+    /// Dependency name to the version it asks for, empty for local path dependencies.
+    #[serde(default)]
+    pub deps: BTreeMap<String, String>,
+}
+
+/// How a cell behaves on bleeding compared to stable.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Comparison {
+    Regression,
+    Fix,
+    BrokenOnBoth,
+    OkOnBoth,
+}
+
+impl Comparison {
+    pub fn classify(stable: Status, bleeding: Status) -> Comparison {
+        match (stable.is_success(), bleeding.is_success()) {
+            (true, false) => Comparison::Regression,
+            (false, true) => Comparison::Fix,
+            (false, false) => Comparison::BrokenOnBoth,
+            (true, true) => Comparison::OkOnBoth,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendComparison {
+    pub wasm: Comparison,
+    pub wasm_gc: Comparison,
+    pub js: Comparison,
+}
+
+impl BackendComparison {
+    pub fn get(&self, backend: Backend) -> Comparison {
+        match backend {
+            Backend::Wasm => self.wasm,
+            Backend::WasmGC => self.wasm_gc,
+            Backend::Js => self.js,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CBTComparison {
+    pub check: BackendComparison,
+    pub build: BackendComparison,
+    pub test: BackendComparison,
+}
+
+impl CBTComparison {
+    pub fn get(&self, cmd: MoonCommand) -> Comparison {
+        match cmd {
+            MoonCommand::Check(backend) => self.check.get(backend),
+            MoonCommand::Build(backend) => self.build.get(backend),
+            MoonCommand::Test(backend) => self.test.get(backend),
+        }
+    }
+}
+
+/// Mirrors `BuildState`: one entry per version, `None` when either toolchain
+/// has no data for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonState {
+    pub source: usize,
+    pub cbts: Vec<Option<CBTComparison>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceFlakiness {
+    pub source: String,
+    pub runs: usize,
+    pub flaky_runs: usize,
+    pub rate: f64,
+}
+
+/// Share of source versions passing one cell.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellHealth {
+    pub cell: String,
+    pub passed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthScore {
+    pub toolchain: ToolChainLabel,
+    pub cells: Vec<CellHealth>,
+    /// Percentage of passing cells, averaged over source versions with flagship packages weighted up.
+    pub score: f64,
+    /// Registry packages with at least one version passing a `build` cell.
+    pub registry_building: usize,
+    pub registry_total: usize,
+}
+
+#[cfg(test)]
+fn test_sources(names: &[&str]) -> Vec<MooncakeSource> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| MooncakeSource::MooncakesIO {
+            name: name.to_string(),
+            version: vec!["0.1.0".to_string()],
+            index,
+            yanked: vec![],
+            registry: None,
+        })
+        .collect()
+}
+
+/// A run of registry sources at version 0.1.0 with nothing built yet, its
+/// stable and bleeding toolchains at `moon 0.1.0` and `moon 0.2.0`.
+#[cfg(test)]
+pub(crate) fn test_dashboard(run_id: &str, sources: &[&str]) -> MoonBuildDashboard {
+    let version = |label, version: &str| ToolChainVersion {
+        label,
+        moon_version: format!("moon {}", version),
+        moonc_version: format!("v{}", version),
+    };
+    MoonBuildDashboard {
+        run_id: run_id.to_string(),
+        run_number: run_id.to_string(),
+        start_time: String::new(),
+        sources: test_sources(sources),
+        stable_toolchain_version: version(ToolChainLabel::Stable, "0.1.0"),
+        stable_release_data: vec![],
+        bleeding_toolchain_version: version(ToolChainLabel::Bleeding, "0.2.0"),
+        bleeding_release_data: vec![],
+        toolchain_comparison: vec![],
+        flakiness: vec![],
+        health: vec![],
+    }
+}
+
+/// A build of `revision` of the source at `source`, every cell at `status`.
+#[cfg(test)]
+pub(crate) fn test_build(source: usize, revision: &str, status: Status) -> BuildState {
+    let result = ExecuteResult {
+        status,
+        start_time: String::new(),
+        elapsed: 1,
+        attempts: vec![],
+        diagnostics: vec![],
+        carried_over: None,
+    };
+    let backends = BackendState {
+        wasm: result.clone(),
+        wasm_gc: result.clone(),
+        js: result,
+    };
+    BuildState {
+        source,
+        cbts: vec![Some(CBT {
+            check: backends.clone(),
+            build: backends.clone(),
+            test: backends,
+        })],
+        revisions: vec![Some(revision.to_string())],
+        modules: vec![None],
+        resolutions: vec![ResolutionMode::AsPublished],
+        upstream: None,
+        integrity: BTreeMap::new(),
+    }
+}
+
+#[test]
+fn source_slug() {
+    let git = MooncakeSource::Git {
+        url: "https://github.com/moonbitlang/core.git".to_string(),
+        rev: vec![],
+        index: 0,
+    };
+    assert_eq!(git.slug(), "github.com-moonbitlang-core");
+    let registry = MooncakeSource::MooncakesIO {
+        name: "Yoorkin/example/list".to_string(),
+        version: vec![],
+        index: 3,
+        yanked: vec![],
+        registry: None,
+    };
+    assert_eq!(registry.slug(), "yoorkin-example-list");
+    let private = MooncakeSource::MooncakesIO {
+        name: "a/lib".to_string(),
+        version: vec![],
+        index: 4,
+        yanked: vec![],
+        registry: Some("acme".to_string()),
+    };
+    assert_eq!(private.slug(), "acme-a-lib");
+}
+
+#[test]
+fn parse_cell() {
+    for cmd in MoonCommand::all() {
+        assert_eq!(cmd.to_string().parse::<MoonCommand>(), Ok(cmd));
+    }
+    assert!("run:js".parse::<MoonCommand>().is_err());
 }
@@ -0,0 +1,296 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    bisect::version_key,
+    dashboard::{
+        Cell, ModuleInfo, MoonBuildDashboard, MoonCommand, MooncakeSource, Status, ToolChainLabel,
+    },
+    resolve,
+};
+
+/// Reads the module name and dependencies from `moon.mod.json` in `workdir`.
+pub fn read_module(workdir: &Path) -> Option<ModuleInfo> {
+    let content = std::fs::read_to_string(workdir.join("moon.mod.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let name = json.get("name")?.as_str()?.to_string();
+    let deps = json
+        .get("deps")
+        .and_then(|deps| deps.as_object())
+        .map(|deps| {
+            deps.iter()
+                .map(|(dep, req)| {
+                    // "0.4.6", { "version": "0.4.6" } or { "path": "../x" }
+                    let version = req
+                        .as_str()
+                        .or_else(|| req.get("version").and_then(|v| v.as_str()))
+                        .unwrap_or("");
+                    (dep.clone(), version.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(ModuleInfo { name, deps })
+}
+
+/// A failing cell whose dependency fails the same cell too.
+#[derive(Debug, Serialize)]
+pub struct Blocked {
+    pub toolchain: ToolChainLabel,
+    pub source: String,
+    pub version: String,
+    pub cmd: MoonCommand,
+    /// The failing dependency, as `name version`.
+    pub upstream: String,
+    /// The failing dependency at the end of the chain, which is not blocked itself.
+    pub root: String,
+}
+
+type Node<'a> = (&'a MooncakeSource, &'a str);
+
+/// Whether a tracked `version` of `source` can be what a dependency on `req`
+/// resolves to: any version of a git source, which tracks a branch, and for
+/// sources of the public registry, which moon installs dependencies from, one
+/// at least the lowest `req` allows, in the same major version.
+fn satisfies(source: &MooncakeSource, version: &str, req: &str) -> bool {
+    // local path dependencies have no version
+    if req.is_empty() {
+        return false;
+    }
+    if let MooncakeSource::Git { .. } = source {
+        return true;
+    }
+    if source.registry().is_some() {
+        return false;
+    }
+    let Some(minimum) = resolve::minimum(req) else {
+        return false;
+    };
+    let (version, minimum) = (version_key(version), version_key(minimum));
+    !version.is_empty() && version.first() == minimum.first() && version >= minimum
+}
+
+fn module_name<'a>(cell: &Cell<'a>) -> &'a str {
+    cell.module.map_or(cell.source.name(), |m| m.name.as_str())
+}
+
+/// Finds failing cells of tracked sources that depend on another tracked
+/// source failing the same cell, on the same toolchain.
+pub fn blocked_failures(run: &MoonBuildDashboard) -> Vec<Blocked> {
+    let mut blocked = vec![];
+    for label in ToolChainLabel::all() {
+        let cells = run.cells(label);
+        let mut by_module: HashMap<&str, Vec<Node>> = HashMap::new();
+        let mut deps: HashMap<Node, &BTreeMap<String, String>> = HashMap::new();
+        let mut failing: HashSet<(Node, MoonCommand)> = HashSet::new();
+        for cell in &cells {
+            let node = (cell.source, cell.version);
+            if cell.cmd == MoonCommand::all()[0] {
+                by_module.entry(module_name(cell)).or_default().push(node);
+                if let Some(module) = cell.module {
+                    deps.insert(node, &module.deps);
+                }
+            }
+            if cell.result.status == Status::Failure {
+                failing.insert((node, cell.cmd));
+            }
+        }
+
+        // the tracked dependency of `node` failing `cmd`, at the lowest tracked
+        // version its requirement allows, as version selection would pick
+        let upstream = |node: Node, cmd: MoonCommand| -> Option<Node> {
+            deps.get(&node)?.iter().find_map(|(dep, req)| {
+                let candidate = by_module
+                    .get(dep.as_str())?
+                    .iter()
+                    .filter(|candidate| {
+                        **candidate != node && satisfies(candidate.0, candidate.1, req)
+                    })
+                    .copied()
+                    .min_by_key(|(_, version)| version_key(version))?;
+                failing.contains(&(candidate, cmd)).then_some(candidate)
+            })
+        };
+
+        for cell in &cells {
+            let node = (cell.source, cell.version);
+            if !failing.contains(&(node, cell.cmd)) {
+                continue;
+            }
+            let Some(direct) = upstream(node, cell.cmd) else {
+                continue;
+            };
+            let mut root = direct;
+            let mut seen = HashSet::from([node, direct]);
+            while let Some(next) = upstream(root, cell.cmd) {
+                if !seen.insert(next) {
+                    break;
+                }
+                root = next;
+            }
+            blocked.push(Blocked {
+                toolchain: label,
+                source: node.0.full_name(),
+                version: node.1.to_string(),
+                cmd: cell.cmd,
+                upstream: format!("{} {}", direct.0.full_name(), direct.1),
+                root: format!("{} {}", root.0.full_name(), root.1),
+            });
+        }
+    }
+    blocked
+}
+
+/// Keys of blocked cells, for skipping them in reports of root failures.
+pub fn blocked_keys(blocked: &[Blocked]) -> HashSet<(ToolChainLabel, &str, &str, MoonCommand)> {
+    blocked
+        .iter()
+        .map(|b| (b.toolchain, b.source.as_str(), b.version.as_str(), b.cmd))
+        .collect()
+}
+
+#[cfg(test)]
+fn with_module(
+    mut state: crate::dashboard::BuildState,
+    name: &str,
+    deps: &[(&str, &str)],
+) -> crate::dashboard::BuildState {
+    let deps = deps
+        .iter()
+        .map(|(dep, req)| (dep.to_string(), req.to_string()))
+        .collect();
+    state.modules = vec![Some(ModuleInfo {
+        name: name.to_string(),
+        deps,
+    })];
+    state
+}
+
+#[test]
+fn downstream_failures_are_blocked() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // c/c depends on b/b, which depends on a/a; all three fail on bleeding
+    let mut run = test_dashboard("1", &["a/a", "b/b", "c/c"]);
+    run.bleeding_release_data = vec![
+        with_module(test_build(0, "0.1.0", Status::Failure), "a/a", &[]),
+        with_module(
+            test_build(1, "0.1.0", Status::Failure),
+            "b/b",
+            &[("a/a", "0.1.0")],
+        ),
+        with_module(
+            test_build(2, "0.1.0", Status::Failure),
+            "c/c",
+            &[("b/b", "0.1.0")],
+        ),
+    ];
+
+    let blocked = blocked_failures(&run);
+    let summary: Vec<(&str, &str, &str)> = blocked
+        .iter()
+        .filter(|b| b.cmd == MoonCommand::all()[0])
+        .map(|b| (b.source.as_str(), b.upstream.as_str(), b.root.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("b/b", "a/a 0.1.0", "a/a 0.1.0"),
+            ("c/c", "b/b 0.1.0", "a/a 0.1.0")
+        ]
+    );
+    assert_eq!(blocked.len(), 2 * MoonCommand::all().len());
+    assert!(blocked
+        .iter()
+        .all(|b| b.toolchain == ToolChainLabel::Bleeding));
+}
+
+#[test]
+fn requirements_are_minimum_versions() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    let blocked_by = |req: &str| {
+        let mut run = test_dashboard("1", &["a/a", "b/b"]);
+        run.stable_release_data = vec![
+            with_module(test_build(0, "0.1.3", Status::Failure), "a/a", &[]),
+            with_module(
+                test_build(1, "0.1.0", Status::Failure),
+                "b/b",
+                &[("a/a", req)],
+            ),
+        ];
+        if let MooncakeSource::MooncakesIO { version, .. } = &mut run.sources[0] {
+            *version = vec!["0.1.3".to_string()];
+        }
+        blocked_failures(&run).len()
+    };
+    assert_eq!(blocked_by("0.1.0"), MoonCommand::all().len());
+    assert_eq!(blocked_by(">=0.1.2, <0.2.0"), MoonCommand::all().len());
+    // the tracked version is too old, or of another major version
+    assert_eq!(blocked_by("0.1.4"), 0);
+    assert_eq!(blocked_by("1.0.0"), 0);
+    assert_eq!(blocked_by(""), 0);
+}
+
+#[test]
+fn git_tracked_upstreams_match_by_module() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // moonbitlang/x is tracked at `main` and breaks the registry package using it
+    let mut run = test_dashboard("1", &["a/app"]);
+    run.sources.push(MooncakeSource::Git {
+        url: "https://github.com/moonbitlang/x".to_string(),
+        rev: vec!["main".to_string()],
+        index: 1,
+    });
+    let mut app = test_build(0, "0.1.0", Status::Success);
+    app.cbts[0].as_mut().unwrap().build.js.status = Status::Failure;
+    let mut x = test_build(1, "abc1234", Status::Success);
+    x.cbts[0].as_mut().unwrap().build.js.status = Status::Failure;
+    run.stable_release_data = vec![
+        with_module(app, "a/app", &[("moonbitlang/x", "0.4.6")]),
+        with_module(x, "moonbitlang/x", &[]),
+    ];
+
+    let blocked = blocked_failures(&run);
+    assert_eq!(blocked.len(), 1);
+    assert_eq!(blocked[0].source, "a/app");
+    assert_eq!(blocked[0].cmd, MoonCommand::all()[5]);
+    assert_eq!(blocked[0].upstream, "https://github.com/moonbitlang/x main");
+}
+
+#[test]
+fn private_registry_packages_are_not_upstreams() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // a/a is tracked on the public registry and on acme, and b/b depends on it;
+    // only the acme copy fails, but moon installs a/a from the public registry
+    let mut run = test_dashboard("1", &["a/a", "a/a", "b/b"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[1] {
+        *registry = Some("acme".to_string());
+    }
+    let deps = [("a/a", "0.1.0")];
+    run.stable_release_data = vec![
+        with_module(test_build(0, "0.1.0", Status::Success), "a/a", &[]),
+        with_module(test_build(1, "0.1.0", Status::Failure), "a/a", &[]),
+        with_module(test_build(2, "0.1.0", Status::Failure), "b/b", &deps),
+    ];
+    assert!(blocked_failures(&run).is_empty());
+
+    // an acme package depending on a failing public one is named with its registry
+    run.stable_release_data[0] = with_module(test_build(0, "0.1.0", Status::Failure), "a/a", &[]);
+    run.stable_release_data[1] = with_module(test_build(1, "0.1.0", Status::Failure), "a/a", &deps);
+    let blocked = blocked_failures(&run);
+    let sources: HashSet<(&str, &str)> = blocked
+        .iter()
+        .map(|b| (b.source.as_str(), b.upstream.as_str()))
+        .collect();
+    assert_eq!(
+        sources,
+        HashSet::from([("acme:a/a", "a/a 0.1.0"), ("b/b", "a/a 0.1.0")])
+    );
+}
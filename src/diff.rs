@@ -0,0 +1,380 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    dashboard::{MoonBuildDashboard, MooncakeSource, Status, ToolChainLabel, ToolChainVersion},
+    flaky::is_flaky,
+};
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CellChange {
+    Regressed,
+    Fixed,
+    StillBroken,
+    New,
+    Removed,
+    /// Regressed in a source that is often flaky, so not counted as a regression.
+    Flaky,
+    StillPassing,
+}
+
+impl CellChange {
+    fn text(&self) -> &str {
+        match self {
+            CellChange::Regressed => "regressed",
+            CellChange::Fixed => "fixed",
+            CellChange::StillBroken => "still broken",
+            CellChange::New => "new",
+            CellChange::Removed => "removed",
+            CellChange::Flaky => "flaky",
+            CellChange::StillPassing => "still passing",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CellDiff {
+    pub source: String,
+    pub registry: Option<String>,
+    pub toolchain: ToolChainLabel,
+    pub cell: String,
+    pub change: CellChange,
+    pub before_version: Option<String>,
+    pub after_version: Option<String>,
+    pub before: Option<Status>,
+    pub after: Option<Status>,
+}
+
+impl CellDiff {
+    fn source_text(&self) -> String {
+        match &self.registry {
+            Some(registry) => format!("{}:{}", registry, self.source),
+            None => self.source.clone(),
+        }
+    }
+
+    fn version_text(&self) -> String {
+        match (&self.before_version, &self.after_version) {
+            (Some(before), Some(after)) if before != after => format!("{} -> {}", before, after),
+            (_, Some(version)) | (Some(version), None) => version.clone(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolChainChange {
+    pub toolchain: ToolChainLabel,
+    pub before: ToolChainVersion,
+    pub after: ToolChainVersion,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunDiff {
+    pub from: String,
+    pub to: String,
+    pub toolchain_changes: Vec<ToolChainChange>,
+    pub cells: Vec<CellDiff>,
+}
+
+/// Key identifying a cell across runs. Sources are matched by slug rather
+/// than `index`, so reordering `repos.txt` does not produce spurious changes,
+/// and versions are paired separately, see [`pair_versions`].
+type CellKey = (String, ToolChainLabel, String);
+
+struct CellVersion<'a> {
+    source: &'a MooncakeSource,
+    version: &'a str,
+    status: Status,
+}
+
+fn cell_statuses(run: &MoonBuildDashboard) -> BTreeMap<CellKey, Vec<CellVersion<'_>>> {
+    let mut cells: BTreeMap<CellKey, Vec<CellVersion>> = BTreeMap::new();
+    for label in ToolChainLabel::all() {
+        for cell in run.cells(label) {
+            cells
+                .entry((cell.source.slug(), label, cell.cmd.to_string()))
+                .or_default()
+                .push(CellVersion {
+                    source: cell.source,
+                    version: cell.version,
+                    status: cell.result.status,
+                });
+        }
+    }
+    cells
+}
+
+/// Pairs the versions of a cell in two runs: the same version with itself,
+/// then the remaining ones in order, so that a `latest` source moving to a
+/// new version is compared with the version it replaced.
+fn pair_versions<'a, 'b>(
+    before: &'a [CellVersion<'b>],
+    after: &'a [CellVersion<'b>],
+) -> Vec<(Option<&'a CellVersion<'b>>, Option<&'a CellVersion<'b>>)> {
+    let mut pairs = vec![];
+    let mut gone = vec![];
+    for b in before {
+        match after.iter().find(|a| a.version == b.version) {
+            Some(a) => pairs.push((Some(b), Some(a))),
+            None => gone.push(b),
+        }
+    }
+    let added: Vec<_> = after
+        .iter()
+        .filter(|a| before.iter().all(|b| b.version != a.version))
+        .collect();
+    for i in 0..gone.len().max(added.len()) {
+        pairs.push((gone.get(i).copied(), added.get(i).copied()));
+    }
+    pairs
+}
+
+fn classify(before: Option<Status>, after: Option<Status>) -> CellChange {
+    match (
+        before.map(|s| s.is_success()),
+        after.map(|s| s.is_success()),
+    ) {
+        (Some(true), Some(false)) => CellChange::Regressed,
+        (Some(false), Some(true)) => CellChange::Fixed,
+        (Some(false), Some(false)) => CellChange::StillBroken,
+        (Some(true), Some(true)) => CellChange::StillPassing,
+        (None, _) => CellChange::New,
+        (_, None) => CellChange::Removed,
+    }
+}
+
+pub fn diff_runs(a: &MoonBuildDashboard, b: &MoonBuildDashboard) -> RunDiff {
+    let mut toolchain_changes = vec![];
+    for label in ToolChainLabel::all() {
+        let before = a.toolchain_version(label);
+        let after = b.toolchain_version(label);
+        if before.moon_version != after.moon_version || before.moonc_version != after.moonc_version
+        {
+            toolchain_changes.push(ToolChainChange {
+                toolchain: label,
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+    }
+
+    let before = cell_statuses(a);
+    let after = cell_statuses(b);
+    let mut keys: Vec<&CellKey> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut cells = vec![];
+    for key in keys {
+        let (_, toolchain, cell) = key;
+        let none = vec![];
+        let pairs = pair_versions(
+            before.get(key).unwrap_or(&none),
+            after.get(key).unwrap_or(&none),
+        );
+        for (x, y) in pairs {
+            let Some(source) = y.or(x).map(|v| v.source) else {
+                continue;
+            };
+            let change = match classify(x.map(|v| v.status), y.map(|v| v.status)) {
+                CellChange::Regressed if is_flaky(b, source) => CellChange::Flaky,
+                change => change,
+            };
+            cells.push(CellDiff {
+                source: source.name().to_string(),
+                registry: source.registry().map(|r| r.to_string()),
+                toolchain: *toolchain,
+                cell: cell.clone(),
+                change,
+                before_version: x.map(|v| v.version.to_string()),
+                after_version: y.map(|v| v.version.to_string()),
+                before: x.map(|v| v.status),
+                after: y.map(|v| v.status),
+            });
+        }
+    }
+    cells.sort_by_key(|c| c.change);
+
+    RunDiff {
+        from: a.run_id.clone(),
+        to: b.run_id.clone(),
+        toolchain_changes,
+        cells,
+    }
+}
+
+/// Renders the diff as a terminal table. Cells that pass in both runs are
+/// only listed when `all` is set.
+pub fn render_table(diff: &RunDiff, all: bool) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", format!("{} -> {}", diff.from, diff.to).bold());
+    for change in &diff.toolchain_changes {
+        let _ = writeln!(
+            out,
+            "{} toolchain: {} / {} -> {} / {}",
+            change.toolchain,
+            change.before.moon_version,
+            change.before.moonc_version,
+            change.after.moon_version,
+            change.after.moonc_version
+        );
+    }
+
+    let rows: Vec<&CellDiff> = diff
+        .cells
+        .iter()
+        .filter(|c| all || c.change != CellChange::StillPassing)
+        .collect();
+    let source_width = rows
+        .iter()
+        .map(|c| c.source_text().len())
+        .max()
+        .unwrap_or(0);
+    let version_width = rows
+        .iter()
+        .map(|c| c.version_text().len())
+        .max()
+        .unwrap_or(0);
+    for c in rows {
+        let change = format!("{:<13}", c.change.text());
+        let change = match c.change {
+            CellChange::Regressed => change.red().bold(),
+            CellChange::Fixed => change.green().bold(),
+            CellChange::StillBroken => change.red(),
+            CellChange::New | CellChange::Removed | CellChange::Flaky => change.yellow(),
+            CellChange::StillPassing => change.normal(),
+        };
+        let _ = writeln!(
+            out,
+            "{} {:<sw$} {:<vw$} {:<8} {}",
+            change,
+            c.source_text(),
+            c.version_text(),
+            c.toolchain.to_string(),
+            c.cell,
+            sw = source_width,
+            vw = version_width,
+        );
+    }
+
+    let count = |change| diff.cells.iter().filter(|c| c.change == change).count();
+    let _ = writeln!(
+        out,
+        "{} regressed, {} fixed, {} still broken, {} new, {} removed, {} flaky",
+        count(CellChange::Regressed),
+        count(CellChange::Fixed),
+        count(CellChange::StillBroken),
+        count(CellChange::New),
+        count(CellChange::Removed),
+        count(CellChange::Flaky)
+    );
+    out
+}
+
+#[cfg(test)]
+fn set_source(run: &mut MoonBuildDashboard, index: usize, versions: &[&str], from: Option<&str>) {
+    if let MooncakeSource::MooncakesIO {
+        version, registry, ..
+    } = &mut run.sources[index]
+    {
+        *version = versions.iter().map(|v| v.to_string()).collect();
+        *registry = from.map(|r| r.to_string());
+    }
+}
+
+#[cfg(test)]
+fn changes(diff: &RunDiff) -> Vec<(String, String, &str, CellChange)> {
+    diff.cells
+        .iter()
+        .filter(|c| c.change != CellChange::StillPassing)
+        .map(|c| (c.source_text(), c.version_text(), c.cell.as_str(), c.change))
+        .collect()
+}
+
+#[test]
+fn diff_matches_sources_by_slug() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // b/lib of the public registry and of `acme` are different packages
+    let mut a = test_dashboard("1", &["a/lib", "b/lib", "b/lib"]);
+    set_source(&mut a, 2, &["0.1.0"], Some("acme"));
+    let mut broken = test_build(2, "0.1.0", Status::Success);
+    broken.cbts[0].as_mut().unwrap().check.js.status = Status::Failure;
+    a.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Success),
+        test_build(1, "0.1.0", Status::Success),
+        broken,
+    ];
+
+    // reordered, with acme's b/lib fixed and the public one regressed
+    let mut b = test_dashboard("2", &["b/lib", "b/lib", "a/lib"]);
+    set_source(&mut b, 0, &["0.1.0"], Some("acme"));
+    let mut regressed = test_build(1, "0.1.0", Status::Success);
+    regressed.cbts[0].as_mut().unwrap().build.wasm.status = Status::Failure;
+    b.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Success),
+        regressed,
+        test_build(2, "0.1.0", Status::Success),
+    ];
+
+    let diff = diff_runs(&a, &b);
+    assert_eq!(
+        changes(&diff),
+        vec![
+            (
+                "b/lib".to_string(),
+                "0.1.0".to_string(),
+                "build:wasm",
+                CellChange::Regressed
+            ),
+            (
+                "acme:b/lib".to_string(),
+                "0.1.0".to_string(),
+                "check:js",
+                CellChange::Fixed
+            ),
+        ]
+    );
+}
+
+#[test]
+fn diff_follows_sources_to_new_versions() {
+    use crate::dashboard::{test_build, test_dashboard, MoonCommand};
+
+    // a/lib is `latest` and moved to 0.2.0, c/lib stopped listing 0.1.0
+    let mut a = test_dashboard("1", &["a/lib", "c/lib"]);
+    set_source(&mut a, 1, &["0.1.0", "0.2.0"], None);
+    let mut both = test_build(1, "0.1.0", Status::Success);
+    both.cbts.push(both.cbts[0].clone());
+    both.revisions.push(Some("0.2.0".to_string()));
+    a.stable_release_data = vec![test_build(0, "0.1.0", Status::Success), both];
+
+    let mut b = test_dashboard("2", &["a/lib", "c/lib"]);
+    set_source(&mut b, 0, &["0.2.0"], None);
+    set_source(&mut b, 1, &["0.2.0"], None);
+    let mut regressed = test_build(0, "0.2.0", Status::Success);
+    regressed.cbts[0].as_mut().unwrap().build.js.status = Status::Failure;
+    b.stable_release_data = vec![regressed, test_build(1, "0.2.0", Status::Success)];
+
+    let diff = diff_runs(&a, &b);
+    let changes = changes(&diff);
+    assert_eq!(
+        changes[0],
+        (
+            "a/lib".to_string(),
+            "0.1.0 -> 0.2.0".to_string(),
+            "build:js",
+            CellChange::Regressed
+        )
+    );
+    assert_eq!(changes.len(), 1 + MoonCommand::all().len());
+    assert!(changes[1..]
+        .iter()
+        .all(|(source, version, _, change)| source == "c/lib"
+            && version == "0.1.0"
+            && *change == CellChange::Removed));
+    assert!(render_table(&diff, false).contains("a/lib 0.1.0 -> 0.2.0 stable   build:js"));
+}
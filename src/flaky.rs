@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use crate::dashboard::{
+    MoonBuildDashboard, MooncakeSource, SourceFlakiness, Status, ToolChainLabel,
+};
+
+/// Number of recent runs the flakiness rate is computed over.
+pub const FLAKINESS_WINDOW: usize = 30;
+
+/// Sources that were flaky in at least this fraction of recent runs do not
+/// count as regressions.
+pub const FLAKY_RATE_THRESHOLD: f64 = 0.2;
+
+/// Computes, for every source, the fraction of `runs` in which at least one
+/// of its cells was `Flaky` on either toolchain.
+pub fn flakiness<'a>(
+    runs: impl IntoIterator<Item = &'a MoonBuildDashboard>,
+) -> Vec<SourceFlakiness> {
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for run in runs {
+        let mut flaky: BTreeMap<String, bool> = BTreeMap::new();
+        for label in ToolChainLabel::all() {
+            // carried-over cells were not built in this run
+            for cell in run.cells(label) {
+                if cell.result.carried_over.is_some() {
+                    continue;
+                }
+                *flaky.entry(cell.source.full_name()).or_default() |=
+                    cell.result.status == Status::Flaky;
+            }
+        }
+        for (source, flaky) in flaky {
+            let entry = counts.entry(source).or_default();
+            entry.0 += 1;
+            entry.1 += usize::from(flaky);
+        }
+    }
+    counts
+        .into_iter()
+        .map(|(source, (runs, flaky_runs))| SourceFlakiness {
+            source,
+            runs,
+            flaky_runs,
+            rate: flaky_runs as f64 / runs as f64,
+        })
+        .collect()
+}
+
+pub fn is_flaky(run: &MoonBuildDashboard, source: &MooncakeSource) -> bool {
+    let source = source.full_name();
+    run.flakiness
+        .iter()
+        .any(|f| f.source == source && f.rate >= FLAKY_RATE_THRESHOLD)
+}
+
+#[test]
+fn flakiness_rate_per_source() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    let flaky_build = |source: usize| {
+        let mut state = test_build(source, "0.1.0", Status::Success);
+        state.cbts[0].as_mut().unwrap().test.js.status = Status::Flaky;
+        state
+    };
+    let mut runs = vec![];
+    for i in 0..4 {
+        let mut run = test_dashboard(&i.to_string(), &["a/a", "b/b"]);
+        // a/a is flaky on bleeding every other run, b/b is only built from run 2 on
+        let a = if i % 2 == 0 {
+            flaky_build(0)
+        } else {
+            test_build(0, "0.1.0", Status::Success)
+        };
+        run.stable_release_data = vec![test_build(0, "0.1.0", Status::Success)];
+        run.bleeding_release_data = vec![a];
+        if i >= 2 {
+            run.stable_release_data
+                .push(test_build(1, "0.1.0", Status::Success));
+        }
+        runs.push(run);
+    }
+    // a flaky result carried over by an incremental run is not counted again
+    let mut carried = runs[0].clone();
+    carried.bleeding_release_data[0].cbts[0]
+        .as_mut()
+        .unwrap()
+        .test
+        .js
+        .carried_over = Some("0".to_string());
+    runs.push(carried);
+
+    let rates = flakiness(&runs);
+    assert_eq!(rates.len(), 2);
+    assert_eq!((rates[0].source.as_str(), rates[0].flaky_runs), ("a/a", 2));
+    assert_eq!(rates[0].runs, 5);
+    assert_eq!(rates[0].rate, 0.4);
+    assert_eq!((rates[1].source.as_str(), rates[1].runs), ("b/b", 2));
+    assert_eq!(rates[1].rate, 0.0);
+}
+
+#[test]
+fn flaky_versions_count_once_per_run() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // both tracked versions of a/a are flaky in the same run
+    let mut run = test_dashboard("1", &["a/a"]);
+    if let MooncakeSource::MooncakesIO { version, .. } = &mut run.sources[0] {
+        *version = vec!["0.1.0".to_string(), "0.2.0".to_string()];
+    }
+    let mut state = test_build(0, "0.1.0", Status::Flaky);
+    state.cbts.push(state.cbts[0].clone());
+    run.stable_release_data = vec![state];
+    let rates = flakiness([&run]);
+    assert_eq!((rates[0].runs, rates[0].flaky_runs), (1, 1));
+
+    run.flakiness = rates;
+    assert!(is_flaky(&run, &run.sources[0]));
+}
+
+#[test]
+fn registries_are_told_apart() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // a/lib is flaky on the public registry only
+    let mut run = test_dashboard("1", &["a/lib", "a/lib"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[1] {
+        *registry = Some("acme".to_string());
+    }
+    run.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Flaky),
+        test_build(1, "0.1.0", Status::Success),
+    ];
+    run.flakiness = flakiness([&run]);
+    let rates: Vec<(&str, usize)> = run
+        .flakiness
+        .iter()
+        .map(|f| (f.source.as_str(), f.flaky_runs))
+        .collect();
+    assert_eq!(rates, vec![("a/lib", 1), ("acme:a/lib", 0)]);
+    assert!(is_flaky(&run, &run.sources[0]));
+    assert!(!is_flaky(&run, &run.sources[1]));
+}
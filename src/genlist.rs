@@ -0,0 +1,200 @@
+use std::collections::BTreeSet;
+
+use crate::mooncakesio::MooncakesDB;
+
+/// First line of the generated part of a source list. Everything before it,
+/// and after [`GENERATED_END`], is written by hand and kept as is.
+pub const GENERATED_HEADER: &str = "# generated list to test mooncakes on mooncakes.io";
+pub const GENERATED_END: &str = "# end of generated list";
+
+#[derive(Debug, Clone, Default)]
+pub struct GenListFilter {
+    /// Only packages of these owners, all owners if empty.
+    pub owners: Vec<String>,
+    /// Only packages whose latest non-yanked version has one of these keywords, any if empty.
+    pub keywords: Vec<String>,
+    /// Only packages whose latest non-yanked version was published on or after this
+    /// date, compared as an RFC 3339 prefix such as `2024-09-01`.
+    pub updated_since: Option<String>,
+    /// Number of latest versions to list for each package.
+    pub versions: usize,
+}
+
+/// Lists `name latest <versions>` entries of every package in `db` matching `filter`.
+pub fn generate(db: &MooncakesDB, filter: &GenListFilter) -> Vec<(String, String)> {
+    let mut entries = vec![];
+    for (name, versions) in db.packages() {
+        // a yanked latest version says nothing about the package any more
+        let Some(latest) = versions.iter().rev().find(|v| !v.yanked) else {
+            continue;
+        };
+        if !filter.owners.is_empty()
+            && !filter
+                .owners
+                .iter()
+                .any(|owner| name.split('/').next() == Some(owner.as_str()))
+        {
+            continue;
+        }
+        if !filter.keywords.is_empty() {
+            let keywords = latest.keywords.as_deref().unwrap_or_default();
+            if !filter.keywords.iter().any(|k| keywords.contains(k)) {
+                continue;
+            }
+        }
+        if let Some(since) = &filter.updated_since {
+            match &latest.created_at {
+                Some(created_at) if created_at.as_str() >= since.as_str() => {}
+                _ => continue,
+            }
+        }
+        let mut picked: Vec<&str> = versions
+            .iter()
+            .rev()
+            .filter(|v| !v.yanked)
+            .take(filter.versions.max(1))
+            .map(|v| v.version.as_str())
+            .collect();
+        picked.reverse();
+        entries.push((
+            name.to_string(),
+            format!("{} latest {}", name, picked.join(" ")),
+        ));
+    }
+    entries
+}
+
+fn entry_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        None
+    } else {
+        line.split_whitespace().next()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ManifestUpdate {
+    pub content: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Replaces the generated part of `existing` with `entries`, appending it when
+/// there is none. Packages listed by hand are left out of the generated part.
+pub fn update_manifest(existing: &str, entries: &[(String, String)]) -> ManifestUpdate {
+    let lines: Vec<&str> = existing.lines().collect();
+    let start = lines.iter().position(|l| l.trim() == GENERATED_HEADER);
+    let (before, generated, after) = match start {
+        Some(start) => {
+            let end = lines[start..]
+                .iter()
+                .position(|l| l.trim() == GENERATED_END)
+                .map(|i| start + i);
+            match end {
+                Some(end) => (&lines[..start], &lines[start + 1..end], &lines[end + 1..]),
+                None => (&lines[..start], &lines[start + 1..], &lines[lines.len()..]),
+            }
+        }
+        None => (&lines[..], &lines[lines.len()..], &lines[lines.len()..]),
+    };
+
+    let by_hand: BTreeSet<&str> = before
+        .iter()
+        .chain(after)
+        .filter_map(|l| entry_name(l))
+        .collect();
+    let old: BTreeSet<&str> = generated.iter().filter_map(|l| entry_name(l)).collect();
+    let entries: Vec<&(String, String)> = entries
+        .iter()
+        .filter(|(name, _)| !by_hand.contains(name.as_str()))
+        .collect();
+    let new: BTreeSet<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+
+    let mut content = String::new();
+    for line in before {
+        content.push_str(line);
+        content.push('\n');
+    }
+    if start.is_none() && !before.is_empty() && before.last() != Some(&"") {
+        content.push('\n');
+    }
+    content.push_str(GENERATED_HEADER);
+    content.push('\n');
+    for (_, line) in &entries {
+        content.push_str(line);
+        content.push('\n');
+    }
+    content.push_str(GENERATED_END);
+    content.push('\n');
+    for line in after {
+        content.push_str(line);
+        content.push('\n');
+    }
+
+    ManifestUpdate {
+        content,
+        added: new.difference(&old).map(|s| s.to_string()).collect(),
+        removed: old.difference(&new).map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn manifest_keeps_hand_written_lines() {
+    let existing = "https://github.com/moonbitlang/core main\n# pinned\na/pinned 0.1.0\n\n# generated list to test mooncakes on mooncakes.io\na/old latest 0.1.0\na/kept latest 0.1.0\n";
+    let entries = vec![
+        ("a/kept".to_string(), "a/kept latest 0.2.0".to_string()),
+        ("a/new".to_string(), "a/new latest 1.0.0".to_string()),
+        ("a/pinned".to_string(), "a/pinned latest 0.3.0".to_string()),
+    ];
+    let update = update_manifest(existing, &entries);
+    assert_eq!(
+        update.content,
+        "https://github.com/moonbitlang/core main\n# pinned\na/pinned 0.1.0\n\n# generated list to test mooncakes on mooncakes.io\na/kept latest 0.2.0\na/new latest 1.0.0\n# end of generated list\n"
+    );
+    assert_eq!(update.added, vec!["a/new"]);
+    assert_eq!(update.removed, vec!["a/old"]);
+
+    // the hand-written tail after the generated part survives a second update
+    let tailed = format!("{}# more by hand\nb/b 0.1.0\n", update.content);
+    let again = update_manifest(&tailed, &entries);
+    assert!(again
+        .content
+        .ends_with("# end of generated list\n# more by hand\nb/b 0.1.0\n"));
+    assert!(again.added.is_empty() && again.removed.is_empty());
+}
+
+#[test]
+fn filters_use_the_latest_version_not_yanked() {
+    use crate::mooncakesio::{get_mooncakes_at, index_of_pkg};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let path = index_of_pkg(tmp.path(), "a", "lib");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(
+        path,
+        [
+            r#"{"name":"a/lib","version":"0.1.0","keywords":["json"],"created_at":"2024-09-01T00:00:00Z"}"#,
+            r#"{"name":"a/lib","version":"0.2.0","yanked":true,"keywords":["http"],"created_at":"2024-10-01T00:00:00Z"}"#,
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    let db = get_mooncakes_at(tmp.path()).unwrap();
+    let generate_with = |keyword: &str, since: &str| {
+        generate(
+            &db,
+            &GenListFilter {
+                keywords: vec![keyword.to_string()],
+                updated_since: Some(since.to_string()),
+                ..Default::default()
+            },
+        )
+    };
+    assert_eq!(
+        generate_with("json", "2024-09-01"),
+        vec![("a/lib".to_string(), "a/lib latest 0.1.0".to_string())]
+    );
+    assert!(generate_with("http", "2024-09-01").is_empty());
+    assert!(generate_with("json", "2024-10-01").is_empty());
+}
@@ -72,3 +72,76 @@ pub fn git_checkout(workdir: &Path, rev: &str) -> Result<(), GitOpsError> {
     }
     Ok(())
 }
+
+fn git_output(workdir: &Path, args: &[&str]) -> Result<String, GitOpsError> {
+    let output = std::process::Command::new("git")
+        .current_dir(workdir)
+        .args(args)
+        .output()
+        .map_err(GitOpsError::IOError)?;
+    if !output.status.success() {
+        return Err(GitOpsError::ReturnNonZero(output.status));
+    }
+    String::from_utf8(output.stdout).map_err(GitOpsError::Utf8Error)
+}
+
+/// Throws away changes to tracked files in the working tree.
+pub fn git_discard_changes(workdir: &Path) -> Result<(), GitOpsError> {
+    git_output(workdir, &["checkout", "--", "."])?;
+    Ok(())
+}
+
+/// Resolves `rev` to a full commit hash.
+pub fn git_rev_parse(workdir: &Path, rev: &str) -> Result<String, GitOpsError> {
+    Ok(git_output(
+        workdir,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", rev)],
+    )?
+    .trim()
+    .to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+}
+
+pub fn get_commit_info(workdir: &Path, rev: &str) -> Result<CommitInfo, GitOpsError> {
+    let output = git_output(
+        workdir,
+        &["show", "-s", "--format=%H%x00%an <%ae>%x00%aI%x00%s", rev],
+    )?;
+    let mut parts = output.trim_end().splitn(4, '\0').map(|s| s.to_string());
+    let mut next = || parts.next().unwrap_or_default();
+    Ok(CommitInfo {
+        hash: next(),
+        author: next(),
+        date: next(),
+        subject: next(),
+    })
+}
+
+/// Starts a bisection between `good` and `bad` and checks out the first commit to test.
+pub fn git_bisect_start(workdir: &Path, bad: &str, good: &str) -> Result<(), GitOpsError> {
+    git_output(workdir, &["bisect", "start", bad, good])?;
+    Ok(())
+}
+
+/// Marks the checked out commit and returns the first bad commit once the
+/// bisection is finished, otherwise checks out the next commit to test.
+pub fn git_bisect_mark(workdir: &Path, good: bool) -> Result<Option<String>, GitOpsError> {
+    let term = if good { "good" } else { "bad" };
+    let output = git_output(workdir, &["bisect", term])?;
+    Ok(output.lines().find_map(|line| {
+        line.strip_suffix(" is the first bad commit")
+            .map(|hash| hash.to_string())
+    }))
+}
+
+pub fn git_bisect_reset(workdir: &Path) -> Result<(), GitOpsError> {
+    git_output(workdir, &["bisect", "reset"])?;
+    Ok(())
+}
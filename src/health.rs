@@ -0,0 +1,191 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use crate::dashboard::{
+    CellHealth, HealthScore, MoonBuildDashboard, MoonCommand, MooncakeSource, ToolChainLabel,
+};
+
+/// Packages weighted up in the health score unless others are given.
+pub const DEFAULT_FLAGSHIPS: &[&str] = &["moonbitlang/core", "moonbitlang/x"];
+const FLAGSHIP_WEIGHT: f64 = 5.0;
+
+fn is_flagship(source: &MooncakeSource, module: Option<&str>, flagships: &[String]) -> bool {
+    let name = source.name().trim_end_matches(".git");
+    flagships
+        .iter()
+        .any(|f| module == Some(f.as_str()) || name == f || name.ends_with(&format!("/{}", f)))
+}
+
+/// Computes the health of each toolchain in a run. `flagships` are module
+/// names, or git URLs ending in them, whose source versions weigh more.
+pub fn health_scores(run: &MoonBuildDashboard, flagships: &[String]) -> Vec<HealthScore> {
+    let mut scores = vec![];
+    for label in ToolChainLabel::all() {
+        let all_cells = run.cells(label);
+
+        let cells = MoonCommand::all()
+            .into_iter()
+            .map(|cmd| {
+                let cells: Vec<_> = all_cells.iter().filter(|c| c.cmd == cmd).collect();
+                CellHealth {
+                    cell: cmd.to_string(),
+                    passed: cells
+                        .iter()
+                        .filter(|c| c.result.status.is_success())
+                        .count(),
+                    total: cells.len(),
+                }
+            })
+            .collect();
+
+        // (passed, total, weight) of each source version
+        let mut versions: HashMap<(&MooncakeSource, &str), (usize, usize, f64)> = HashMap::new();
+        let mut registry = BTreeSet::new();
+        let mut registry_building = BTreeSet::new();
+        for cell in &all_cells {
+            let weight =
+                if is_flagship(cell.source, cell.module.map(|m| m.name.as_str()), flagships) {
+                    FLAGSHIP_WEIGHT
+                } else {
+                    1.0
+                };
+            let entry = versions
+                .entry((cell.source, cell.version))
+                .or_insert((0, 0, weight));
+            entry.1 += 1;
+            if cell.result.status.is_success() {
+                entry.0 += 1;
+            }
+            if let MooncakeSource::MooncakesIO {
+                name, registry: r, ..
+            } = cell.source
+            {
+                let package = (r.as_deref(), name.as_str());
+                registry.insert(package);
+                if matches!(cell.cmd, MoonCommand::Build(_)) && cell.result.status.is_success() {
+                    registry_building.insert(package);
+                }
+            }
+        }
+        let (weighted, weights) =
+            versions
+                .values()
+                .fold((0.0, 0.0), |(sum, weights), (passed, total, weight)| {
+                    (
+                        sum + weight * *passed as f64 / *total as f64,
+                        weights + weight,
+                    )
+                });
+
+        scores.push(HealthScore {
+            toolchain: label,
+            cells,
+            score: if weights > 0.0 {
+                weighted * 100.0 / weights
+            } else {
+                0.0
+            },
+            registry_building: registry_building.len(),
+            registry_total: registry.len(),
+        });
+    }
+    scores
+}
+
+/// Uses the health stored with a run, computing it with the default flagships for older runs.
+pub fn run_health(run: &MoonBuildDashboard) -> Vec<HealthScore> {
+    if run.health.is_empty() {
+        let flagships: Vec<String> = DEFAULT_FLAGSHIPS.iter().map(|f| f.to_string()).collect();
+        health_scores(run, &flagships)
+    } else {
+        run.health.clone()
+    }
+}
+
+/// Renders the health of each run as a table, oldest first.
+pub fn render_health(runs: &[MoonBuildDashboard]) -> String {
+    let mut out = String::new();
+    let _ = write!(out, "{:>6}  {:<25}", "run", "started");
+    for label in ToolChainLabel::all() {
+        let _ = write!(out, "  {:>8} {:>9}", label, "building");
+    }
+    let _ = writeln!(out);
+    for run in runs {
+        let _ = write!(
+            out,
+            "{:>6}  {:<25}",
+            run.run_number,
+            run.start_time.chars().take(25).collect::<String>()
+        );
+        let health = run_health(run);
+        for label in ToolChainLabel::all() {
+            match health.iter().find(|h| h.toolchain == label) {
+                Some(h) => {
+                    let _ = write!(
+                        out,
+                        "  {:>7.1}% {:>9}",
+                        h.score,
+                        format!("{}/{}", h.registry_building, h.registry_total)
+                    );
+                }
+                None => {
+                    let _ = write!(out, "  {:>8} {:>9}", "-", "-");
+                }
+            }
+        }
+        let _ = writeln!(out);
+    }
+    out
+}
+
+#[test]
+fn flagships_weigh_more() {
+    use crate::dashboard::{test_build, test_dashboard, Status};
+
+    // a/a passes everything, b/b fails build:js on stable, bleeding built nothing
+    let mut run = test_dashboard("1", &["a/a", "b/b"]);
+    let mut failing = test_build(1, "0.1.0", Status::Success);
+    failing.cbts[0].as_mut().unwrap().build.js.status = Status::Failure;
+    run.stable_release_data = vec![test_build(0, "0.1.0", Status::Success), failing];
+
+    let plain = health_scores(&run, &[]);
+    let weighted = health_scores(&run, &["b/b".to_string()]);
+    assert!((plain[0].score - (1.0 + 8.0 / 9.0) * 50.0).abs() < 1e-9);
+    assert!((weighted[0].score - (1.0 + 5.0 * 8.0 / 9.0) / 6.0 * 100.0).abs() < 1e-9);
+    let build_js = plain[0]
+        .cells
+        .iter()
+        .find(|c| c.cell == "build:js")
+        .unwrap();
+    assert_eq!((build_js.passed, build_js.total), (1, 2));
+    assert_eq!(
+        (plain[0].registry_building, plain[0].registry_total),
+        (2, 2)
+    );
+    assert_eq!(plain[1].toolchain, ToolChainLabel::Bleeding);
+    assert_eq!(plain[1].score, 0.0);
+    assert_eq!(plain[1].registry_total, 0);
+}
+
+#[test]
+fn registries_are_told_apart() {
+    use crate::dashboard::{test_build, test_dashboard, Status};
+
+    // a/lib passes on the public registry and fails on acme
+    let mut run = test_dashboard("1", &["a/lib", "a/lib"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[1] {
+        *registry = Some("acme".to_string());
+    }
+    run.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Success),
+        test_build(1, "0.1.0", Status::Failure),
+    ];
+    let scores = health_scores(&run, &[]);
+    assert_eq!(scores[0].score, 50.0);
+    assert_eq!(
+        (scores[0].registry_building, scores[0].registry_total),
+        (1, 2)
+    );
+}
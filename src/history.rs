@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use crate::dashboard::MoonBuildDashboard;
+
+/// Runs recorded by `stat`, one JSON object per line.
+pub const DEFAULT_DATA_FILE: &str = "data/data.jsonl";
+
+#[derive(Debug, thiserror::Error)]
+#[error("history error")]
+pub struct HistoryError {
+    #[source]
+    kind: HistoryErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("invalid record at line {line}")]
+    Serde {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("run not found: {0}")]
+    RunNotFound(String),
+    #[error("no runs recorded")]
+    Empty,
+}
+
+/// Loads every run recorded in a `data.jsonl` file, oldest first.
+pub fn load_runs(path: &Path) -> Result<Vec<MoonBuildDashboard>, HistoryError> {
+    let content = std::fs::read_to_string(path).map_err(|e| HistoryError {
+        kind: HistoryErrorKind::IOError(e),
+    })?;
+    let mut runs = vec![];
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let run = serde_json::from_str(line).map_err(|e| HistoryError {
+            kind: HistoryErrorKind::Serde {
+                line: i + 1,
+                source: e,
+            },
+        })?;
+        runs.push(run);
+    }
+    Ok(runs)
+}
+
+/// Picks a run by `run_id` or `run_number`, or the latest one when `id` is `None`.
+pub fn select_run<'a>(
+    runs: &'a [MoonBuildDashboard],
+    id: Option<&str>,
+) -> Result<&'a MoonBuildDashboard, HistoryError> {
+    match id {
+        Some(id) => runs
+            .iter()
+            .rev()
+            .find(|run| run.run_id == id || run.run_number == id)
+            .ok_or(HistoryError {
+                kind: HistoryErrorKind::RunNotFound(id.to_string()),
+            }),
+        None => runs.last().ok_or(HistoryError {
+            kind: HistoryErrorKind::Empty,
+        }),
+    }
+}
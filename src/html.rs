@@ -0,0 +1,288 @@
+use std::fmt::Write;
+
+use crate::{
+    dashboard::{
+        ExecuteResult, MoonBuildDashboard, MoonCommand, MooncakeSource, Status, ToolChainLabel,
+    },
+    report::pass_rate,
+};
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; background: #f3f4f6; margin: 0; padding: 1rem; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.2rem; margin-top: 2rem; }
+table { border-collapse: collapse; background: #fff; box-shadow: 0 1px 3px rgba(0,0,0,.1); font-size: .85rem; }
+th, td { padding: .4rem .8rem; border: 1px solid #e5e7eb; }
+th { background: #e5e7eb; }
+th.stable { background: #22c55e; color: #fff; }
+th.bleeding { background: #dc2626; color: #fff; }
+th small { display: block; font-weight: normal; }
+td.ok { background: #bbf7d0; color: #166534; text-align: right; }
+td.fail { background: #fecaca; color: #991b1b; text-align: center; }
+td.flaky { background: #fef08a; color: #854d0e; text-align: right; }
+td.limit { background: #fed7aa; color: #9a3412; text-align: center; }
+td.sandbox { background: #e9d5ff; color: #6b21a8; text-align: center; }
+td.diff { outline: 2px solid #facc15; outline-offset: -2px; }
+td.carried { opacity: 0.6; }
+td.na { color: #6b7280; text-align: center; }
+a { color: #2563eb; text-decoration: none; }
+footer { margin-top: 2rem; color: #6b7280; font-size: .8rem; }
+"#;
+
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// Prefix for links to published files, e.g. `/moon-build-dashboard/`.
+    pub base_path: String,
+    /// Number of previous runs listed in the history table.
+    pub history: usize,
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn source_link(source: &MooncakeSource) -> String {
+    match source {
+        MooncakeSource::Git { url, .. } => format!(
+            r#"<a href="{}">{}</a>"#,
+            escape(url),
+            escape(url.trim_start_matches("https://github.com/"))
+        ),
+        MooncakeSource::MooncakesIO {
+            name,
+            registry: Some(registry),
+            ..
+        } => format!("{} ({})", escape(name), escape(registry)),
+        MooncakeSource::MooncakesIO { name, .. } => format!(
+            r#"<a href="https://mooncakes.io/docs/#/{}/">{}</a>"#,
+            escape(name),
+            escape(name)
+        ),
+    }
+}
+
+fn result_cell(out: &mut String, result: &ExecuteResult, other: Option<&ExecuteResult>) {
+    let diff = match other {
+        Some(other) if other.status.is_success() != result.status.is_success() => " diff",
+        _ => "",
+    };
+    // carried over results are dimmed
+    let classes = match result.carried_over {
+        Some(_) => format!("{} carried", diff),
+        None => diff.to_string(),
+    };
+    match result.status {
+        Status::Success => {
+            let _ = write!(out, r#"<td class="ok{}">{}</td>"#, classes, result.elapsed);
+        }
+        Status::Failure => {
+            let _ = write!(out, r#"<td class="fail{}">x</td>"#, classes);
+        }
+        Status::ResourceExceeded { kind } => {
+            let _ = write!(
+                out,
+                r#"<td class="limit{}" title="{} limit exceeded">!</td>"#,
+                classes, kind
+            );
+        }
+        Status::SandboxViolation => {
+            let _ = write!(
+                out,
+                r#"<td class="sandbox{}" title="{}">!</td>"#,
+                classes,
+                escape(result.diagnostics.first().map_or("", |d| d.as_str()))
+            );
+        }
+        Status::Flaky => {
+            let _ = write!(
+                out,
+                r#"<td class="flaky{}" title="passed after {} attempts">{}~</td>"#,
+                classes,
+                result.attempts.len(),
+                result.elapsed
+            );
+        }
+    }
+}
+
+fn toolchain_rate(run: &MoonBuildDashboard, label: ToolChainLabel) -> String {
+    let cells = run.cells(label);
+    let passed = cells
+        .iter()
+        .filter(|c| c.result.status.is_success())
+        .count();
+    pass_rate(passed, cells.len())
+}
+
+/// Renders a standalone HTML page for `runs.last()`, with a table of the
+/// previous runs below it. No scripts or external stylesheets are referenced.
+pub fn render_html(runs: &[MoonBuildDashboard], options: &HtmlOptions) -> String {
+    let mut out = String::new();
+    let Some(run) = runs.last() else {
+        return out;
+    };
+    let cmds = MoonCommand::all();
+
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, r#"<html lang="en">"#);
+    let _ = writeln!(out, "<head>");
+    let _ = writeln!(out, r#"<meta charset="utf-8">"#);
+    let _ = writeln!(out, "<title>Moon Build Dashboard</title>");
+    let _ = writeln!(out, "<style>{}</style>", STYLE);
+    let _ = writeln!(out, "</head>");
+    let _ = writeln!(out, "<body>");
+    let _ = writeln!(out, "<h1>Moon Build Dashboard</h1>");
+    let _ = writeln!(
+        out,
+        "<p>Run {} ({}), started at {}</p>",
+        escape(&run.run_number),
+        escape(&run.run_id),
+        escape(&run.start_time)
+    );
+
+    let _ = writeln!(out, "<table>");
+    let _ = writeln!(out, "<thead>");
+    let _ = write!(
+        out,
+        r#"<tr><th rowspan="3">Repository</th><th rowspan="3">Version</th>"#
+    );
+    for label in ToolChainLabel::all() {
+        let version = run.toolchain_version(label);
+        let title = match label {
+            ToolChainLabel::Stable => "Stable Release",
+            ToolChainLabel::Bleeding => "Bleeding Edge Release",
+        };
+        let _ = write!(
+            out,
+            r#"<th colspan="{}" class="{}">{}<small>{} / moonc {}</small></th>"#,
+            cmds.len(),
+            label,
+            title,
+            escape(&version.moon_version),
+            escape(&version.moonc_version)
+        );
+    }
+    let _ = writeln!(out, "</tr>");
+    let _ = write!(out, "<tr>");
+    for _ in ToolChainLabel::all() {
+        for name in ["Check", "Build", "Test"] {
+            let _ = write!(out, r#"<th colspan="3">{}(ms)</th>"#, name);
+        }
+    }
+    let _ = writeln!(out, "</tr>");
+    let _ = write!(out, "<tr>");
+    for _ in ToolChainLabel::all() {
+        for cmd in &cmds {
+            let _ = write!(out, "<th>{}</th>", cmd.backend().to_flag());
+        }
+    }
+    let _ = writeln!(out, "</tr>");
+    let _ = writeln!(out, "</thead>");
+
+    let _ = writeln!(out, "<tbody>");
+    for source in &run.sources {
+        let versions = source.versions();
+        for (version_index, version) in versions.iter().enumerate() {
+            let _ = write!(out, "<tr>");
+            if version_index == 0 {
+                let _ = write!(
+                    out,
+                    r#"<td rowspan="{}">{}</td>"#,
+                    versions.len(),
+                    source_link(source)
+                );
+            }
+            let _ = write!(out, "<td>{}</td>", escape(version));
+            for label in ToolChainLabel::all() {
+                let index = source.get_index();
+                if run.cell(label, index, version_index, cmds[0]).is_none() {
+                    let _ = write!(
+                        out,
+                        r#"<td colspan="{}" class="na">No {} data available</td>"#,
+                        cmds.len(),
+                        label
+                    );
+                    continue;
+                }
+                for cmd in &cmds {
+                    if let Some(result) = run.cell(label, index, version_index, *cmd) {
+                        // highlight bleeding cells that differ from stable
+                        let other = match label {
+                            ToolChainLabel::Stable => None,
+                            ToolChainLabel::Bleeding => {
+                                run.cell(ToolChainLabel::Stable, index, version_index, *cmd)
+                            }
+                        };
+                        result_cell(&mut out, result, other);
+                    }
+                }
+            }
+            let _ = writeln!(out, "</tr>");
+        }
+    }
+    let _ = writeln!(out, "</tbody>");
+    let _ = writeln!(out, "</table>");
+
+    let _ = writeln!(out, "<h2>Recent runs</h2>");
+    let _ = writeln!(out, "<table>");
+    let _ = writeln!(
+        out,
+        "<tr><th>Run</th><th>Started</th><th>Stable</th><th>Pass rate</th><th>Bleeding</th><th>Pass rate</th></tr>"
+    );
+    for run in runs.iter().rev().take(options.history) {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&run.run_number),
+            escape(&run.start_time),
+            escape(&run.stable_toolchain_version.moonc_version),
+            toolchain_rate(run, ToolChainLabel::Stable),
+            escape(&run.bleeding_toolchain_version.moonc_version),
+            toolchain_rate(run, ToolChainLabel::Bleeding),
+        );
+    }
+    let _ = writeln!(out, "</table>");
+
+    let _ = writeln!(
+        out,
+        r#"<footer>Raw data: <a href="{}data.jsonl">data.jsonl</a></footer>"#,
+        escape(&options.base_path)
+    );
+    let _ = writeln!(out, "</body>");
+    let _ = writeln!(out, "</html>");
+    out
+}
+
+#[test]
+fn html_escapes_untrusted_names() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // a private registry name is untrusted too
+    let mut run = test_dashboard("1", &["<script>/x", "a/lib"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[1] {
+        *registry = Some("<b>acme".to_string());
+    }
+    let mut carried = test_build(1, "0.1.0", Status::Success);
+    carried.cbts[0].as_mut().unwrap().check.js.carried_over = Some("0".to_string());
+    run.stable_release_data = vec![test_build(0, "0.1.0", Status::Success), carried];
+    let options = HtmlOptions {
+        base_path: "/".to_string(),
+        history: 10,
+    };
+    let page = render_html(&[run], &options);
+    assert!(!page.contains("<script>") && !page.contains("<b>"));
+    assert!(page.contains("&lt;script&gt;/x"));
+    assert!(page.contains("a/lib (&lt;b&gt;acme)"));
+    assert_eq!(page.matches(r#"<td class="ok carried">"#).count(), 1);
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use crate::dashboard::{
+    ExecuteResult, ModuleInfo, MoonBuildDashboard, MooncakeSource, ResolutionMode, ToolChainLabel,
+    ToolChainVersion, CBT,
+};
+
+/// Source slug, revision built and dependency resolution mode.
+type BuildKey = (String, String, ResolutionMode);
+
+#[derive(Debug, Clone)]
+struct CachedBuild {
+    run_id: String,
+    module: Option<ModuleInfo>,
+    cbt: CBT,
+}
+
+/// Results recorded by earlier runs with one toolchain version, reused by
+/// incremental runs instead of rebuilding an unchanged source revision.
+#[derive(Debug, Clone, Default)]
+pub struct ResultCache {
+    builds: HashMap<BuildKey, CachedBuild>,
+}
+
+fn results_mut(cbt: &mut CBT) -> impl Iterator<Item = &mut ExecuteResult> {
+    [&mut cbt.check, &mut cbt.build, &mut cbt.test]
+        .into_iter()
+        .flat_map(|b| [&mut b.wasm, &mut b.wasm_gc, &mut b.js])
+}
+
+impl ResultCache {
+    /// Collects the results `runs` recorded with `toolchain`, on either label,
+    /// the latest run winning. Builds without a recorded revision are skipped,
+    /// and so are builds with latest dependencies, which change between runs.
+    pub fn from_runs(runs: &[MoonBuildDashboard], toolchain: &ToolChainVersion) -> ResultCache {
+        let mut builds = HashMap::new();
+        for run in runs {
+            for label in ToolChainLabel::all() {
+                let version = run.toolchain_version(label);
+                if version.moon_version != toolchain.moon_version
+                    || version.moonc_version != toolchain.moonc_version
+                {
+                    continue;
+                }
+                for state in run.release_data(label) {
+                    let Some(source) = run.sources.get(state.source) else {
+                        continue;
+                    };
+                    for (i, cbt) in state.cbts.iter().enumerate() {
+                        let (Some(cbt), Some(Some(revision))) = (cbt, state.revisions.get(i))
+                        else {
+                            continue;
+                        };
+                        let resolution = state.resolutions.get(i).copied().unwrap_or_default();
+                        if resolution == ResolutionMode::Latest {
+                            continue;
+                        }
+                        // a carried over result keeps pointing at the run that built it
+                        let run_id = cbt
+                            .check
+                            .wasm
+                            .carried_over
+                            .clone()
+                            .unwrap_or_else(|| run.run_id.clone());
+                        builds.insert(
+                            (source.slug(), revision.clone(), resolution),
+                            CachedBuild {
+                                run_id,
+                                module: state.modules.get(i).cloned().flatten(),
+                                cbt: cbt.clone(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        ResultCache { builds }
+    }
+
+    /// The module and results recorded for `revision` of `source`, every
+    /// result marked as carried over.
+    pub fn get(
+        &self,
+        source: &MooncakeSource,
+        revision: &str,
+        resolution: ResolutionMode,
+    ) -> Option<(Option<ModuleInfo>, CBT)> {
+        if resolution == ResolutionMode::Latest {
+            return None;
+        }
+        let cached = self
+            .builds
+            .get(&(source.slug(), revision.to_string(), resolution))?;
+        let mut cbt = cached.cbt.clone();
+        for result in results_mut(&mut cbt) {
+            result.carried_over = Some(cached.run_id.clone());
+        }
+        Some((cached.module.clone(), cbt))
+    }
+}
+
+#[test]
+fn cache_reuses_results_of_the_same_toolchain() {
+    use crate::dashboard::{test_build, test_dashboard, MoonCommand, Status};
+
+    // a/lib passes on stable and fails on bleeding, a newer toolchain
+    let mut first = test_dashboard("1", &["a/lib"]);
+    first.stable_release_data = vec![test_build(0, "0.1.0", Status::Success)];
+    first.bleeding_release_data = vec![test_build(0, "0.1.0", Status::Failure)];
+    let mut second = test_dashboard("2", &["b/lib"]);
+    second.stable_release_data = vec![test_build(0, "0.1.0", Status::Failure)];
+    let runs = vec![first, second];
+    let a = &runs[0].sources[0];
+    let b = &runs[1].sources[0];
+
+    let stable = runs[0].toolchain_version(ToolChainLabel::Stable).clone();
+    let cache = ResultCache::from_runs(&runs, &stable);
+    let (_, cbt) = cache.get(a, "0.1.0", ResolutionMode::AsPublished).unwrap();
+    let cell = cbt.get(MoonCommand::all()[0]);
+    assert_eq!(cell.status, Status::Success);
+    assert_eq!(cell.carried_over.as_deref(), Some("1"));
+    let (_, cbt) = cache.get(b, "0.1.0", ResolutionMode::AsPublished).unwrap();
+    assert_eq!(
+        cbt.get(MoonCommand::all()[0]).carried_over.as_deref(),
+        Some("2")
+    );
+    assert!(cache.get(a, "0.2.0", ResolutionMode::AsPublished).is_none());
+    assert!(cache.get(a, "0.1.0", ResolutionMode::Latest).is_none());
+
+    // a carried over result is reused with the run that built it
+    let (_, carried) = cache.get(a, "0.1.0", ResolutionMode::AsPublished).unwrap();
+    let mut third = test_dashboard("3", &["a/lib"]);
+    let mut state = test_build(0, "0.1.0", Status::Success);
+    state.cbts[0] = Some(carried);
+    third.stable_release_data = vec![state];
+    let cache = ResultCache::from_runs(&[third], &stable);
+    let (_, cbt) = cache.get(a, "0.1.0", ResolutionMode::AsPublished).unwrap();
+    assert_eq!(
+        cbt.get(MoonCommand::all()[0]).carried_over.as_deref(),
+        Some("1")
+    );
+
+    // builds with latest dependencies are never reused
+    let mut latest = test_dashboard("4", &["a/lib"]);
+    let mut state = test_build(0, "0.1.0", Status::Success);
+    state.resolutions = vec![ResolutionMode::Latest];
+    latest.stable_release_data = vec![state];
+    assert!(ResultCache::from_runs(&[latest], &stable).builds.is_empty());
+
+    let bleeding = runs[0].toolchain_version(ToolChainLabel::Bleeding).clone();
+    let cache = ResultCache::from_runs(&runs, &bleeding);
+    let (_, cbt) = cache.get(a, "0.1.0", ResolutionMode::AsPublished).unwrap();
+    assert_eq!(cbt.get(MoonCommand::all()[0]).status, Status::Failure);
+    assert!(cache.get(b, "0.1.0", ResolutionMode::AsPublished).is_none());
+}
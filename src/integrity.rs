@@ -0,0 +1,173 @@
+use std::path::{Component, Path};
+
+use crate::dashboard::IntegrityViolation;
+
+/// Largest package archive downloaded from a registry.
+pub const MAX_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+/// Largest unpacked size of a package archive.
+pub const MAX_UNPACKED_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Parses the entries of an `unzip -l` listing into their length and name.
+pub fn parse_listing(listing: &str) -> Vec<(u64, String)> {
+    let mut lines = listing.lines();
+    // names start under the `Name` header, and may contain spaces
+    let Some(column) = lines
+        .find(|l| l.trim_start().starts_with("Length"))
+        .and_then(|header| header.find("Name"))
+    else {
+        return vec![];
+    };
+    lines
+        .skip(1)
+        .take_while(|l| !l.starts_with("---------"))
+        .filter_map(|l| {
+            let length = l.split_whitespace().next()?.parse().ok()?;
+            Some((length, l.get(column..)?.to_string()))
+        })
+        .collect()
+}
+
+/// Finds the first symbolic link in a `zipinfo` listing, whose mode starts with `l`.
+pub fn find_symlink(zipinfo: &str) -> Option<IntegrityViolation> {
+    zipinfo
+        .lines()
+        .filter(|l| l.starts_with('l'))
+        .find_map(|l| {
+            // mode, version, system, size, type, method, date, time, then the name
+            let mut rest = l;
+            for _ in 0..8 {
+                rest = rest.trim_start();
+                rest = &rest[rest.find(char::is_whitespace)?..];
+            }
+            Some(IntegrityViolation::Symlink(rest.trim().to_string()))
+        })
+}
+
+/// Checks the entries of an archive before it is unpacked: every entry stays
+/// inside the destination and the unpacked size is within [`MAX_UNPACKED_SIZE`].
+pub fn check_entries(entries: &[(u64, String)]) -> Option<IntegrityViolation> {
+    for (_, name) in entries {
+        let path = Path::new(name);
+        let escapes = name.contains('\\')
+            || path
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            return Some(IntegrityViolation::PathEscape(name.clone()));
+        }
+    }
+    let size: u64 = entries.iter().map(|(length, _)| length).sum();
+    if size > MAX_UNPACKED_SIZE {
+        return Some(IntegrityViolation::TooLarge {
+            size,
+            limit: MAX_UNPACKED_SIZE,
+        });
+    }
+    None
+}
+
+/// Checks an unpacked package: no symlink leaves `workdir`, and its
+/// `moon.mod.json` declares the `name` and `version` of the index entry.
+pub fn check_package(workdir: &Path, name: &str, version: &str) -> Option<IntegrityViolation> {
+    let root = workdir.canonicalize().ok()?;
+    for entry in walkdir::WalkDir::new(workdir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if entry.path_is_symlink() {
+            let inside = entry
+                .path()
+                .canonicalize()
+                .is_ok_and(|target| target.starts_with(&root));
+            if !inside {
+                let path = entry.path().strip_prefix(workdir).unwrap_or(entry.path());
+                return Some(IntegrityViolation::PathEscape(path.display().to_string()));
+            }
+        }
+    }
+
+    let Ok(content) = std::fs::read_to_string(workdir.join("moon.mod.json")) else {
+        return Some(IntegrityViolation::MissingModule);
+    };
+    let json: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(json) => json,
+        Err(e) => return Some(IntegrityViolation::InvalidModule(e.to_string())),
+    };
+    let field = |key: &str| json.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    if field("name") != name {
+        return Some(IntegrityViolation::NameMismatch {
+            expected: name.to_string(),
+            found: field("name").to_string(),
+        });
+    }
+    if field("version") != version {
+        return Some(IntegrityViolation::VersionMismatch {
+            expected: version.to_string(),
+            found: field("version").to_string(),
+        });
+    }
+    None
+}
+
+#[test]
+fn integrity_checks() {
+    let listing = "Archive:  0.1.0.zip\n  Length      Date    Time    Name\n---------  ---------- -----   ----\n        0  2024-09-01 12:00   src/\n        3  2024-09-01 12:00   src/a b.mbt\n       40  2024-09-01 12:00   moon.mod.json\n---------                     -------\n       43                     3 files\n";
+    let entries = parse_listing(listing);
+    assert_eq!(
+        entries,
+        vec![
+            (0, "src/".to_string()),
+            (3, "src/a b.mbt".to_string()),
+            (40, "moon.mod.json".to_string()),
+        ]
+    );
+    assert_eq!(check_entries(&entries), None);
+    assert_eq!(
+        check_entries(&[(1, "src/../../.bashrc".to_string())]),
+        Some(IntegrityViolation::PathEscape(
+            "src/../../.bashrc".to_string()
+        ))
+    );
+    assert_eq!(
+        check_entries(&[(1, "/etc/passwd".to_string())]),
+        Some(IntegrityViolation::PathEscape("/etc/passwd".to_string()))
+    );
+    assert!(matches!(
+        check_entries(&[(MAX_UNPACKED_SIZE + 1, "big".to_string())]),
+        Some(IntegrityViolation::TooLarge { .. })
+    ));
+
+    let zipinfo = "Archive:  0.1.0.zip\nZip file size: 512 bytes, number of entries: 2\n-rw-r--r--  3.0 unx       40 tx stor 24-Sep-01 12:00 moon.mod.json\nlrwxrwxrwx  3.0 unx        4 bx stor 24-Sep-01 12:00 src/etc link\n2 files, 44 bytes uncompressed, 44 bytes compressed:  0.0%\n";
+    assert_eq!(
+        find_symlink(zipinfo),
+        Some(IntegrityViolation::Symlink("src/etc link".to_string()))
+    );
+    assert_eq!(find_symlink(&zipinfo.replace("lrwx", "-rw-")), None);
+
+    let tmp = tempfile::tempdir().unwrap();
+    assert_eq!(
+        check_package(tmp.path(), "a/lib", "0.1.0"),
+        Some(IntegrityViolation::MissingModule)
+    );
+    std::fs::write(
+        tmp.path().join("moon.mod.json"),
+        r#"{"name":"a/lib","version":"0.1.0"}"#,
+    )
+    .unwrap();
+    assert_eq!(check_package(tmp.path(), "a/lib", "0.1.0"), None);
+    assert_eq!(
+        check_package(tmp.path(), "a/lib", "0.2.0"),
+        Some(IntegrityViolation::VersionMismatch {
+            expected: "0.2.0".to_string(),
+            found: "0.1.0".to_string(),
+        })
+    );
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("/etc", tmp.path().join("etc")).unwrap();
+        assert_eq!(
+            check_package(tmp.path(), "a/lib", "0.1.0"),
+            Some(IntegrityViolation::PathEscape("etc".to_string()))
+        );
+    }
+}
@@ -1,5 +1,27 @@
+pub mod badge;
+pub mod bisect;
 pub mod cli;
+pub mod cluster;
 pub mod dashboard;
+pub mod deps;
+pub mod diff;
+pub mod flaky;
+pub mod genlist;
 pub mod git;
+pub mod health;
+pub mod history;
+pub mod html;
+pub mod incremental;
+pub mod integrity;
+pub mod limits;
+pub mod metrics;
 pub mod mooncakesio;
+pub mod perf;
+pub mod regression;
+pub mod report;
+pub mod resolve;
+pub mod rules;
+pub mod sandbox;
+pub mod streak;
+pub mod upstream;
 pub mod util;
@@ -0,0 +1,148 @@
+use std::process::{Command, ExitStatus};
+
+use crate::dashboard::ResourceKind;
+
+/// Signals the kernel sends when the CPU time and file size limits are hit, on Linux.
+const SIGXCPU: i32 = 24;
+const SIGXFSZ: i32 = 25;
+
+/// Output of a failing command hinting at the limit it ran into.
+const PATTERNS: &[(ResourceKind, &str)] = &[
+    (ResourceKind::CpuTime, "SIGXCPU"),
+    (ResourceKind::CpuTime, "CPU time limit exceeded"),
+    (ResourceKind::FileSize, "SIGXFSZ"),
+    (ResourceKind::FileSize, "File size limit exceeded"),
+    (ResourceKind::AddressSpace, "Cannot allocate memory"),
+    (ResourceKind::AddressSpace, "out of memory"),
+    (ResourceKind::AddressSpace, "Out of memory"),
+    (ResourceKind::AddressSpace, "Out_of_memory"),
+    (ResourceKind::AddressSpace, "memory allocation of"),
+    (ResourceKind::Processes, "Resource temporarily unavailable"),
+];
+
+/// Limits applied to every `moon` invocation with `prlimit`, unlimited when `None`.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLimits {
+    /// Bytes of virtual memory.
+    pub address_space: Option<u64>,
+    /// Seconds of CPU time of each process; children such as `moonc` get their own.
+    pub cpu_time: Option<u64>,
+    /// Bytes of the largest file written.
+    pub file_size: Option<u64>,
+    /// Processes of the user running the build, counting those outside it.
+    pub processes: Option<u64>,
+}
+
+impl ResourceLimits {
+    fn get(&self, kind: ResourceKind) -> Option<u64> {
+        match kind {
+            ResourceKind::AddressSpace => self.address_space,
+            ResourceKind::CpuTime => self.cpu_time,
+            ResourceKind::FileSize => self.file_size,
+            ResourceKind::Processes => self.processes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.address_space.is_none()
+            && self.cpu_time.is_none()
+            && self.file_size.is_none()
+            && self.processes.is_none()
+    }
+
+    /// Wraps `cmd` in `prlimit`, keeping its environment and working directory.
+    pub fn wrap(&self, cmd: Command) -> Command {
+        let mut wrapped = Command::new("prlimit");
+        let flags = [
+            ("--as", self.address_space),
+            ("--cpu", self.cpu_time),
+            ("--fsize", self.file_size),
+            ("--nproc", self.processes),
+        ];
+        for (flag, limit) in flags {
+            if let Some(limit) = limit {
+                wrapped.arg(format!("{}={}", flag, limit));
+            }
+        }
+        wrapped
+            .arg("--")
+            .arg(cmd.get_program())
+            .args(cmd.get_args());
+        for (key, value) in cmd.get_envs() {
+            match value {
+                Some(value) => wrapped.env(key, value),
+                None => wrapped.env_remove(key),
+            };
+        }
+        if let Some(dir) = cmd.get_current_dir() {
+            wrapped.current_dir(dir);
+        }
+        wrapped
+    }
+
+    /// The limit a failing command ran into, judged by the signal that
+    /// killed it or its output. Only limits that are set are considered.
+    pub fn exceeded(&self, status: ExitStatus, output: &str) -> Option<ResourceKind> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            let kind = match status.signal() {
+                Some(SIGXCPU) => Some(ResourceKind::CpuTime),
+                Some(SIGXFSZ) => Some(ResourceKind::FileSize),
+                _ => None,
+            };
+            if let Some(kind) = kind.filter(|k| self.get(*k).is_some()) {
+                return Some(kind);
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = status;
+        output.lines().find_map(|line| {
+            PATTERNS
+                .iter()
+                .find(|(kind, pattern)| self.get(*kind).is_some() && line.contains(pattern))
+                .map(|(kind, _)| *kind)
+        })
+    }
+}
+
+#[test]
+fn limits_wrap_and_classify() {
+    let limits = ResourceLimits {
+        address_space: Some(1 << 30),
+        cpu_time: Some(600),
+        ..Default::default()
+    };
+    let mut cmd = Command::new("moon");
+    cmd.args(["build", "--target", "js"])
+        .env("MOON_HOME", "/opt/moon");
+    let wrapped = limits.wrap(cmd);
+    assert_eq!(wrapped.get_program(), "prlimit");
+    let args: Vec<_> = wrapped.get_args().collect();
+    assert_eq!(
+        args,
+        [
+            "--as=1073741824",
+            "--cpu=600",
+            "--",
+            "moon",
+            "build",
+            "--target",
+            "js"
+        ]
+    );
+    assert_eq!(wrapped.get_envs().count(), 1);
+
+    let failed = Command::new("false").status().unwrap();
+    assert_eq!(
+        limits.exceeded(failed, "error: failed to run moonc: signal: 24 (SIGXCPU)"),
+        Some(ResourceKind::CpuTime)
+    );
+    assert_eq!(
+        limits.exceeded(failed, "Fatal error: out of memory."),
+        Some(ResourceKind::AddressSpace)
+    );
+    // no file size limit is set, so this is an ordinary failure
+    assert_eq!(limits.exceeded(failed, "File size limit exceeded"), None);
+    assert_eq!(limits.exceeded(failed, "Error: [4014] type mismatch"), None);
+}
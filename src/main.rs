@@ -1,6 +1,7 @@
 use std::{
-    io::Write,
-    path::Path,
+    collections::{BTreeMap, HashSet},
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
@@ -8,19 +9,25 @@ use chrono::{FixedOffset, Local};
 
 use clap::Parser;
 use colored::Colorize;
+use moon_dashboard::{
+    badge, bisect, cluster, deps, diff, flaky, genlist, git, health, history, html,
+    incremental::ResultCache, limits::ResourceLimits, metrics, perf, regression, report, resolve,
+    rules, sandbox, streak, util::moon_update,
+};
 use moon_dashboard::{
     cli,
     dashboard::{
-        Backend, BackendState, BuildState, ExecuteResult, MoonBuildDashboard, MoonCommand,
-        MooncakeSource, Status, ToolChainLabel, ToolChainVersion, CBT,
+        Attempt, Backend, BackendState, BuildState, ExecuteResult, ModuleInfo, MoonBuildDashboard,
+        MoonCommand, MooncakeSource, ResolutionMode, Status, ToolChainLabel, ToolChainVersion,
+        UpstreamBuild, CBT,
     },
-    mooncakesio,
+    mooncakesio::{self, Registries, Registry, PUBLIC_REGISTRY},
+    util,
     util::{
         get_moon_version, get_moonc_version, install_bleeding_release, install_stable_release,
         MoonOpsError,
     },
 };
-use moon_dashboard::{git, util::moon_update};
 
 #[derive(Debug, thiserror::Error)]
 pub enum RunMoonError {
@@ -28,16 +35,30 @@ pub enum RunMoonError {
     IOError(#[from] std::io::Error),
 
     #[error("non-zero exit code: {0}")]
-    ReturnNonZero(std::process::ExitStatus),
+    ReturnNonZero(std::process::ExitStatus, String),
 
     #[error("from utf8 error")]
     FromUtf8(#[from] std::string::FromUtf8Error),
 }
 
+/// Echoes `pipe` to stderr line by line while collecting it.
+fn tee<R: std::io::Read + Send + 'static>(pipe: R) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut captured = String::new();
+        for line in std::io::BufReader::new(pipe).lines().map_while(Result::ok) {
+            eprintln!("{}", line);
+            captured.push_str(&line);
+            captured.push('\n');
+        }
+        captured
+    })
+}
+
 fn run_moon(
     workdir: &Path,
     source: &MooncakeSource,
     args: &[&str],
+    options: &BuildOptions,
 ) -> Result<Duration, RunMoonError> {
     let start = Instant::now();
     eprintln!(
@@ -46,14 +67,51 @@ fn run_moon(
             .blue()
             .bold()
     );
-    let mut cmd = std::process::Command::new("moon")
+    let mut cmd = match (&options.toolchain, &options.sandbox) {
+        (Some(home), sandbox) => {
+            let bin = home.join("bin");
+            let path = std::env::var_os("PATH").unwrap_or_default();
+            let path = std::env::join_paths(
+                std::iter::once(bin.clone()).chain(std::env::split_paths(&path)),
+            )
+            .map_err(|e| RunMoonError::IOError(std::io::Error::other(e)))?;
+            match sandbox {
+                Some(sandbox) => sandbox.command(&bin.join("moon"), args, workdir, home, &path),
+                None => {
+                    let mut cmd = std::process::Command::new(bin.join("moon"));
+                    cmd.env("MOON_HOME", home).env("PATH", path).args(args);
+                    cmd
+                }
+            }
+        }
+        (None, Some(sandbox)) => sandbox.command(
+            Path::new("moon"),
+            args,
+            workdir,
+            &mooncakesio::home(),
+            &std::env::var_os("PATH").unwrap_or_default(),
+        ),
+        (None, None) => {
+            let mut cmd = std::process::Command::new("moon");
+            cmd.args(args);
+            cmd
+        }
+    };
+    if !options.limits.is_empty() {
+        cmd = options.limits.wrap(cmd);
+    }
+    let mut cmd = cmd
         .current_dir(workdir)
-        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
         .spawn()
         .map_err(|e| RunMoonError::IOError(e))?;
+    let stdout = tee(cmd.stdout.take().unwrap());
+    let stderr = tee(cmd.stderr.take().unwrap());
     let exit = cmd.wait().map_err(|e| RunMoonError::IOError(e))?;
+    let output = stdout.join().unwrap_or_default() + &stderr.join().unwrap_or_default();
     if !exit.success() {
-        return Err(RunMoonError::ReturnNonZero(exit));
+        return Err(RunMoonError::ReturnNonZero(exit, output));
     }
     let elapsed = start.elapsed();
     eprintln!(
@@ -85,14 +143,171 @@ enum GetMooncakeSourcesErrorKind {
 
     #[error("failed on mooncakesdb")]
     MooncakesDB(#[from] mooncakesio::MooncakesDBError),
+
+    #[error("failed to load registry rules")]
+    Rules(#[from] rules::RulesError),
+}
+
+/// Loads the index of `registry` with the packages excluded by the rules at
+/// `rules` removed, printing each excluded package with its rule.
+fn load_registry(
+    registry: &dyn Registry,
+    rules: Option<&Path>,
+) -> Result<mooncakesio::MooncakesDB, GetMooncakeSourcesError> {
+    let rules = rules::RegistryRules::load(rules).map_err(|e| GetMooncakeSourcesError {
+        kind: GetMooncakeSourcesErrorKind::Rules(e),
+    })?;
+    let mut db = registry.get_all().map_err(|e| GetMooncakeSourcesError {
+        kind: GetMooncakeSourcesErrorKind::MooncakesIO(e),
+    })?;
+    db.apply_rules(&rules);
+    for (name, exclusion) in db.excluded() {
+        eprintln!("{}", format!("EXCLUDE {}: {}", name, exclusion).yellow());
+    }
+    Ok(db)
+}
+
+/// Index of every registry, by registry name.
+type RegistryDBs = BTreeMap<String, mooncakesio::MooncakesDB>;
+
+/// Selects every registry package whose latest version depends on the named one.
+const RDEPS_SELECTOR: &str = "rdeps:";
+
+/// Parses one line of a source list such as `repos.txt`, numbering the sources
+/// from `index`. Returns nothing for comments, blank lines and registry packages
+/// missing from their registry in `dbs`, and every matching package for a selector.
+fn parse_source_spec(
+    line: &str,
+    index: usize,
+    dbs: &RegistryDBs,
+) -> Result<Vec<MooncakeSource>, GetMooncakeSourcesError> {
+    let s = line.trim();
+    if s.starts_with("#") || s.is_empty() {
+        return Ok(vec![]);
+    }
+    // acme:a/lib 0.1.0 names the registry the package comes from
+    let (registry, s) = match s.split_once(':') {
+        Some((registry, spec))
+            if !s.starts_with("https://")
+                && !s.starts_with(RDEPS_SELECTOR)
+                && !registry.contains(char::is_whitespace) =>
+        {
+            (Some(registry), spec.trim())
+        }
+        _ => (None, s),
+    };
+    let Some(db) = dbs.get(registry.unwrap_or(PUBLIC_REGISTRY)) else {
+        eprintln!("unknown registry {}", registry.unwrap_or_default());
+        return Ok(vec![]);
+    };
+    let registry = registry
+        .filter(|r| *r != PUBLIC_REGISTRY)
+        .map(|r| r.to_string());
+    if let Some(name) = s.strip_prefix(RDEPS_SELECTOR) {
+        // rdeps:moonbitlang/x
+        let graph = db.dependency_graph();
+        let mut sources = vec![];
+        for rdep in graph.rdeps(name.trim()) {
+            let Ok(version) = db.get_latest_version(rdep) else {
+                eprintln!("{} skipped: every version is yanked", rdep);
+                continue;
+            };
+            sources.push(MooncakeSource::MooncakesIO {
+                name: rdep.to_string(),
+                version: vec![version],
+                index: index + sources.len(),
+                yanked: vec![],
+                registry: registry.clone(),
+            });
+        }
+        if sources.is_empty() {
+            eprintln!("no registry package depends on {}", name.trim());
+        }
+        Ok(sources)
+    } else if s.starts_with("https://") {
+        // https://github.com/moonbitlang/core
+        // https://github.com/moonbitlang/core hash1 hash2 hash3
+        let parts: Vec<&str> = s.split(' ').collect();
+        if parts.len() == 1 {
+            Ok(vec![MooncakeSource::Git {
+                url: parts[0].to_string(),
+                rev: vec!["HEAD".to_string()],
+                index,
+            }])
+        } else {
+            Ok(vec![MooncakeSource::Git {
+                url: parts[0].to_string(),
+                rev: parts[1..].iter().copied().map(|s| s.to_string()).collect(),
+                index,
+            }])
+        }
+    } else {
+        // moonbitlang/core
+        // moonbitlang/core 0.1.0 0.2.0
+        let parts: Vec<&str> = s.split(' ').collect();
+        let name = parts[0].to_string();
+        let mut xs: Vec<String> = parts[1..].iter().copied().map(|s| s.to_string()).collect();
+        if xs.is_empty() {
+            xs.push("latest".to_string());
+        }
+        if let Some(exclusion) = db.excluded().get(&name) {
+            eprintln!("{} excluded: {}", name, exclusion);
+            return Ok(vec![]);
+        }
+        if !db.contains_key(&name) {
+            eprintln!("{} not found", name);
+            return Ok(vec![]);
+        }
+        let mut version = vec![];
+        for s in xs {
+            if s != "latest" {
+                version.push(s);
+            } else if let Ok(latest) = db.get_latest_version(&name) {
+                version.push(latest);
+            } else {
+                eprintln!("{} latest skipped: every version is yanked", name);
+            }
+        }
+        if version.is_empty() {
+            return Ok(vec![]);
+        }
+        version.sort();
+        version.dedup();
+        // yanked versions are only built when named explicitly, and recorded as such
+        let mut yanked = vec![];
+        for v in &version {
+            match db.get_version(&name, v) {
+                Some(info) if info.yanked => {
+                    eprintln!("{}", format!("{} {} is yanked", name, v).yellow());
+                    yanked.push(v.clone());
+                }
+                Some(info) if info.is_deprecated() => {
+                    eprintln!("{}", format!("{} {} is deprecated", name, v).yellow());
+                }
+                _ => {}
+            }
+        }
+        Ok(vec![MooncakeSource::MooncakesIO {
+            name,
+            version,
+            index,
+            yanked,
+            registry,
+        }])
+    }
 }
 
 fn get_mooncake_sources(
     cmd: &cli::StatSubcommand,
+    registries: &Registries,
 ) -> Result<Vec<MooncakeSource>, GetMooncakeSourcesError> {
-    let db = mooncakesio::get_all_mooncakes().map_err(|e| GetMooncakeSourcesError {
-        kind: GetMooncakeSourcesErrorKind::MooncakesIO(e),
-    })?;
+    let mut dbs = RegistryDBs::new();
+    for registry in registries.all() {
+        dbs.insert(
+            registry.name().to_string(),
+            load_registry(registry, cmd.rules.as_deref())?,
+        );
+    }
     let mut repo_list = vec![];
     if let Some(r) = &cmd.repo_url {
         repo_list.push(MooncakeSource::Git {
@@ -106,62 +321,27 @@ fn get_mooncake_sources(
         let content = std::fs::read_to_string(file).map_err(|e| GetMooncakeSourcesError {
             kind: GetMooncakeSourcesErrorKind::IOError(e),
         })?;
+        let mut parsed = vec![];
         for line in content.lines() {
-            let s = line.trim();
-            if s.starts_with("#") || s.trim().is_empty() {
+            let selected = line.contains(RDEPS_SELECTOR);
+            for source in parse_source_spec(line, 0, &dbs)? {
+                parsed.push((source, selected));
+            }
+        }
+        // a package selected by `rdeps:` is built once, and as listed by hand
+        // when it is also listed anywhere else in the file
+        let listed: HashSet<String> = parsed
+            .iter()
+            .filter(|(_, selected)| !selected)
+            .map(|(source, _)| source.slug())
+            .collect();
+        let mut seen = HashSet::new();
+        for (mut source, selected) in parsed {
+            if selected && (listed.contains(&source.slug()) || !seen.insert(source.slug())) {
                 continue;
-            } else if s.starts_with("https://") {
-                // https://github.com/moonbitlang/core
-                // https://github.com/moonbitlang/core hash1 hash2 hash3
-                let parts: Vec<&str> = s.split(' ').collect();
-                if parts.len() == 1 {
-                    repo_list.push(MooncakeSource::Git {
-                        url: parts[0].to_string(),
-                        rev: vec!["HEAD".to_string()],
-                        index: repo_list.len(),
-                    });
-                } else {
-                    repo_list.push(MooncakeSource::Git {
-                        url: parts[0].to_string(),
-                        rev: parts[1..].iter().copied().map(|s| s.to_string()).collect(),
-                        index: repo_list.len(),
-                    });
-                }
-            } else {
-                // moonbitlang/core
-                // moonbitlang/core 0.1.0 0.2.0
-                let parts: Vec<&str> = s.split(' ').collect();
-                let name = parts[0].to_string();
-                let mut xs: Vec<String> =
-                    parts[1..].iter().copied().map(|s| s.to_string()).collect();
-                if xs.is_empty() {
-                    xs.push("latest".to_string());
-                }
-                if !db.contains_key(&name) {
-                    eprintln!("{} not found", name);
-                    continue;
-                }
-                let mut version: Vec<String> = xs
-                    .iter()
-                    .map(|s| {
-                        if s == "latest" {
-                            db.get_latest_version(&name)
-                                .map_err(|e| GetMooncakeSourcesError {
-                                    kind: GetMooncakeSourcesErrorKind::MooncakesDB(e),
-                                })
-                        } else {
-                            Ok(s.to_string())
-                        }
-                    })
-                    .collect::<Result<Vec<String>, GetMooncakeSourcesError>>()?;
-                version.sort();
-                version.dedup();
-                repo_list.push(MooncakeSource::MooncakesIO {
-                    name,
-                    version,
-                    index: repo_list.len(),
-                });
             }
+            source.set_index(repo_list.len());
+            repo_list.push(source);
         }
     }
     Ok(repo_list)
@@ -173,29 +353,124 @@ enum StatMooncakeError {
     RunMoon(#[from] RunMoonError),
 }
 
+/// Options shared by every cell of a `stat` run.
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Number of times a failed cell is rerun before it counts as a failure.
+    pub retries: u32,
+    /// Toolchain to use instead of the one on `PATH`, a directory laid out like `~/.moon`.
+    pub toolchain: Option<PathBuf>,
+    /// How dependency versions are chosen before building.
+    pub resolution: ResolutionMode,
+    /// Also build the upstream repository of registry sources at its default branch.
+    pub upstream: bool,
+    /// Registries that registry sources are downloaded from.
+    pub registries: Registries,
+    /// Earlier results for the toolchain being built with, reused instead of rebuilding.
+    pub cache: Option<ResultCache>,
+    /// Run every `moon` invocation in this sandbox.
+    pub sandbox: Option<sandbox::Sandbox>,
+    /// Resource limits of every `moon` invocation.
+    pub limits: ResourceLimits,
+}
+
+impl From<&cli::StatSubcommand> for BuildOptions {
+    fn from(cmd: &cli::StatSubcommand) -> Self {
+        BuildOptions {
+            retries: cmd.retries,
+            toolchain: None,
+            resolution: cmd.resolution,
+            upstream: cmd.upstream,
+            registries: Registries::default(),
+            cache: None,
+            sandbox: cmd.sandbox.then_some(sandbox::Sandbox {
+                deny_network: cmd.deny_network,
+            }),
+            limits: ResourceLimits {
+                address_space: cmd.limit_memory,
+                cpu_time: cmd.limit_cpu,
+                file_size: cmd.limit_file_size,
+                processes: cmd.limit_processes,
+            },
+        }
+    }
+}
+
 fn stat_mooncake(
     workdir: &Path,
     source: &MooncakeSource,
     cmd: MoonCommand,
+    options: &BuildOptions,
 ) -> Result<ExecuteResult, StatMooncakeError> {
-    let _ = run_moon(workdir, source, &["clean"]);
+    let mut attempts = vec![];
+    let mut status;
+    let mut diagnostics;
+    loop {
+        let _ = run_moon(workdir, source, &["clean"], options);
 
-    let r = run_moon(workdir, source, &cmd.args()).map_err(|e| StatMooncakeError::RunMoon(e));
-    let status = if r.is_err() {
-        Status::Failure
+        let r = run_moon(workdir, source, &cmd.args(), options)
+            .map_err(|e| StatMooncakeError::RunMoon(e));
+        (status, diagnostics) = match &r {
+            Ok(_) => (Status::Success, vec![]),
+            Err(StatMooncakeError::RunMoon(RunMoonError::ReturnNonZero(exit, output))) => {
+                let violation = options
+                    .sandbox
+                    .as_ref()
+                    .and_then(|sandbox| sandbox.find_violation(output, workdir));
+                if let Some(kind) = options.limits.exceeded(*exit, output) {
+                    (
+                        Status::ResourceExceeded { kind },
+                        vec![format!("{} limit exceeded", kind)],
+                    )
+                } else if let Some(violation) = violation {
+                    (Status::SandboxViolation, vec![violation])
+                } else {
+                    (Status::Failure, cluster::extract_diagnostics(output))
+                }
+            }
+            Err(_) => (Status::Failure, vec![]),
+        };
+        let d = r.ok();
+        let start_time = Local::now()
+            .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
+            .format("%Y-%m-%d %H:%M:%S.%3f")
+            .to_string();
+        let elapsed = d.map(|d| d.as_millis() as u64).unwrap_or(0);
+        attempts.push(Attempt {
+            status,
+            start_time,
+            elapsed,
+        });
+        // reruns hit the same sandbox rules and resource limits
+        if matches!(
+            status,
+            Status::Success | Status::SandboxViolation | Status::ResourceExceeded { .. }
+        ) || attempts.len() > options.retries as usize
+        {
+            break;
+        }
+        eprintln!(
+            "{}",
+            format!("RETRY moon {} for {:?}", cmd.args().join(" "), source)
+                .yellow()
+                .bold()
+        );
+    }
+
+    let last = attempts.last().unwrap().clone();
+    let status = if attempts.len() > 1 && last.status == Status::Success {
+        Status::Flaky
     } else {
-        Status::Success
+        last.status
     };
-    let d = r.ok();
-    let start_time = Local::now()
-        .with_timezone(&FixedOffset::east_opt(8 * 3600).unwrap())
-        .format("%Y-%m-%d %H:%M:%S.%3f")
-        .to_string();
-    let elapsed = d.map(|d| d.as_millis() as u64).unwrap_or(0);
     let execute_result = ExecuteResult {
         status,
-        start_time,
-        elapsed,
+        start_time: last.start_time,
+        elapsed: last.elapsed,
+        // only keep the attempts when the cell was rerun
+        attempts: if attempts.len() > 1 { attempts } else { vec![] },
+        diagnostics,
+        carried_over: None,
     };
     Ok(execute_result)
 }
@@ -210,11 +485,150 @@ pub enum BuildError {
     FromUtf8(#[from] std::string::FromUtf8Error),
     #[error("git")]
     GitError(git::GitOpsError),
+    #[error("mooncakesio")]
+    MooncakesIO(mooncakesio::MooncakesIOError),
+    #[error("unknown registry {0}")]
+    UnknownRegistry(String),
+}
+
+/// The registry `source` is downloaded from, the public one for git sources.
+fn source_registry<'a>(
+    source: &MooncakeSource,
+    registries: &'a Registries,
+) -> Result<&'a dyn Registry, BuildError> {
+    registries
+        .get(source.registry())
+        .ok_or_else(|| BuildError::UnknownRegistry(source.registry().unwrap_or_default().into()))
+}
+
+/// Checks out the first revision or downloads the first version of `source`
+/// under `dst`, returning the directory to build in.
+fn fetch_source(
+    source: &MooncakeSource,
+    registries: &Registries,
+    dst: &Path,
+) -> Result<PathBuf, BuildError> {
+    match source {
+        MooncakeSource::Git { url, rev, index: _ } => {
+            git::git_clone_to(url, dst, "test").map_err(BuildError::GitError)?;
+            let workdir = dst.join("test");
+            if let Some(h) = rev.first() {
+                git::git_checkout(&workdir, h).map_err(BuildError::GitError)?;
+            }
+            Ok(workdir)
+        }
+        MooncakeSource::MooncakesIO { name, version, .. } => {
+            let v = version.first().map(|v| v.as_str()).unwrap_or("latest");
+            source_registry(source, registries)?
+                .download_to(name, v, dst)
+                .map_err(BuildError::MooncakesIO)?;
+            Ok(dst.join(v))
+        }
+    }
 }
 
-pub fn build(source: &MooncakeSource) -> Result<BuildState, BuildError> {
+/// Applies the dependency resolution mode to `workdir`, returning whether anything changed.
+/// A source that cannot be resolved is built as published.
+fn resolve_deps(
+    workdir: &Path,
+    source: &MooncakeSource,
+    registry: &dyn Registry,
+    options: &BuildOptions,
+) -> ResolutionMode {
+    if !workdir.join("moon.mod.json").exists() {
+        return ResolutionMode::AsPublished;
+    }
+    match resolve::apply_resolution(workdir, options.resolution, registry) {
+        Ok(changes) => {
+            for c in &changes {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "RESOLVE {} {} -> {} for {}",
+                        c.name,
+                        c.from,
+                        c.to,
+                        source.name()
+                    )
+                    .blue()
+                );
+            }
+            options.resolution
+        }
+        Err(e) => {
+            eprintln!(
+                "Failed to resolve dependencies of {}: {:?}",
+                source.name(),
+                e
+            );
+            ResolutionMode::AsPublished
+        }
+    }
+}
+
+/// Clones the repository the registry index names for `name` and builds its default branch.
+fn build_upstream(
+    name: &str,
+    dst: &Path,
+    source: &MooncakeSource,
+    registry: &dyn Registry,
+    options: &BuildOptions,
+) -> Option<UpstreamBuild> {
+    let repository = match registry.get_package(name) {
+        Ok(versions) => versions.into_iter().rev().find_map(|v| v.repository),
+        Err(e) => {
+            eprintln!("Failed to read the registry index of {}: {:?}", name, e);
+            None
+        }
+    };
+    let Some(repository) = repository.filter(|r| !r.is_empty()) else {
+        eprintln!("{} names no upstream repository", name);
+        return None;
+    };
+    if let Err(e) = git::git_clone_to(&repository, dst, "upstream") {
+        eprintln!("Failed to clone {}: {}", repository, e);
+        return Some(UpstreamBuild {
+            repository,
+            revision: None,
+            cbt: None,
+        });
+    }
+    let workdir = dst.join("upstream");
+    resolve_deps(&workdir, source, registry, options);
+    Some(UpstreamBuild {
+        revision: git::get_git_short_hash(&workdir).ok(),
+        cbt: run_matrix(&workdir, source, options).ok(),
+        repository,
+    })
+}
+
+/// The results an earlier run recorded for `revision` of `source`, in incremental runs.
+fn carried_over(
+    source: &MooncakeSource,
+    revision: Option<&str>,
+    options: &BuildOptions,
+) -> Option<(Option<ModuleInfo>, CBT)> {
+    let revision = revision?;
+    let cached = options
+        .cache
+        .as_ref()?
+        .get(source, revision, options.resolution)?;
+    eprintln!(
+        "{}",
+        format!("CARRY OVER {} {}", source.name(), revision).blue()
+    );
+    Some(cached)
+}
+
+pub fn build(source: &MooncakeSource, options: &BuildOptions) -> Result<BuildState, BuildError> {
     let tmp = tempfile::tempdir().map_err(|e| BuildError::IOError(e))?;
     let mut cbts = vec![];
+    let mut revisions = vec![];
+    let mut modules = vec![];
+    let mut resolutions = vec![];
+    let mut upstream = None;
+    let mut integrity = BTreeMap::new();
+    let registry = source_registry(source, &options.registries)?;
 
     match source {
         MooncakeSource::Git { url, rev, index: _ } => {
@@ -224,24 +638,64 @@ pub fn build(source: &MooncakeSource) -> Result<BuildState, BuildError> {
                 if let Err(e) = git::git_checkout(&workdir, h) {
                     eprintln!("Failed to checkout {}: {}", h, e);
                     cbts.push(None);
+                    revisions.push(None);
+                    modules.push(None);
+                    resolutions.push(ResolutionMode::AsPublished);
                     continue;
                 }
-                cbts.push(run_matrix(&workdir, source).ok());
+                let revision = git::get_git_short_hash(&workdir).ok();
+                if let Some((module, cbt)) = carried_over(source, revision.as_deref(), options) {
+                    cbts.push(Some(cbt));
+                    revisions.push(revision);
+                    modules.push(module);
+                    resolutions.push(options.resolution);
+                    continue;
+                }
+                revisions.push(revision);
+                modules.push(deps::read_module(&workdir));
+                let resolution = resolve_deps(&workdir, source, registry, options);
+                resolutions.push(resolution);
+                cbts.push(run_matrix(&workdir, source, options).ok());
+                // the rewritten moon.mod.json would block checking out the next revision
+                if resolution != ResolutionMode::AsPublished {
+                    git::git_discard_changes(&workdir).map_err(BuildError::GitError)?;
+                }
             }
         }
-        MooncakeSource::MooncakesIO {
-            name,
-            version,
-            index: _,
-        } => {
+        MooncakeSource::MooncakesIO { name, version, .. } => {
             for v in version {
-                if let Err(e) = mooncakesio::download_to(name, v, tmp.path()) {
-                    eprintln!("Failed to download {}/{}: {}", name, v, e);
+                if let Some((module, cbt)) = carried_over(source, Some(v), options) {
+                    cbts.push(Some(cbt));
+                    revisions.push(Some(v.clone()));
+                    modules.push(module);
+                    resolutions.push(options.resolution);
+                    continue;
+                }
+                if let Err(e) = registry.download_to(name, v, tmp.path()) {
+                    match e {
+                        mooncakesio::MooncakesIOError::Integrity(violation) => {
+                            eprintln!(
+                                "{}",
+                                format!("BROKEN PACKAGE {} {}: {}", name, v, violation).red()
+                            );
+                            integrity.insert(v.clone(), violation);
+                        }
+                        e => eprintln!("Failed to download {}/{}: {}", name, v, e),
+                    }
                     cbts.push(None);
+                    revisions.push(None);
+                    modules.push(None);
+                    resolutions.push(ResolutionMode::AsPublished);
                     continue;
                 }
+                revisions.push(Some(v.clone()));
                 let workdir = tmp.path().join(v);
-                cbts.push(run_matrix(&workdir, source).ok());
+                modules.push(deps::read_module(&workdir));
+                resolutions.push(resolve_deps(&workdir, source, registry, options));
+                cbts.push(run_matrix(&workdir, source, options).ok());
+            }
+            if options.upstream {
+                upstream = build_upstream(name, tmp.path(), source, registry, options);
             }
         }
     }
@@ -249,6 +703,11 @@ pub fn build(source: &MooncakeSource) -> Result<BuildState, BuildError> {
     Ok(BuildState {
         source: source.get_index(),
         cbts,
+        revisions,
+        modules,
+        resolutions,
+        upstream,
+        integrity,
     })
 }
 
@@ -258,26 +717,52 @@ enum RunMatrixError {
     StatMooncake(#[from] StatMooncakeError),
 }
 
-fn run_matrix(workdir: &Path, source: &MooncakeSource) -> Result<CBT, RunMatrixError> {
-    let check_wasm = stat_mooncake(workdir, source, MoonCommand::Check(Backend::Wasm))
-        .map_err(|e| RunMatrixError::StatMooncake(e))?;
-    let check_wasm_gc = stat_mooncake(workdir, source, MoonCommand::Check(Backend::WasmGC))
+fn run_matrix(
+    workdir: &Path,
+    source: &MooncakeSource,
+    options: &BuildOptions,
+) -> Result<CBT, RunMatrixError> {
+    // a sandbox without network cannot download dependencies, so fetch them
+    // into the workdir beforehand with the same toolchain and limits
+    if options.sandbox.as_ref().is_some_and(|s| s.deny_network) {
+        let install = BuildOptions {
+            toolchain: options.toolchain.clone(),
+            limits: options.limits.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = run_moon(workdir, source, &["install"], &install) {
+            eprintln!("Failed to install dependencies of {}: {}", source.name(), e);
+        }
+    }
+    let check_wasm = stat_mooncake(workdir, source, MoonCommand::Check(Backend::Wasm), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
-    let check_js = stat_mooncake(workdir, source, MoonCommand::Check(Backend::Js))
+    let check_wasm_gc = stat_mooncake(
+        workdir,
+        source,
+        MoonCommand::Check(Backend::WasmGC),
+        options,
+    )
+    .map_err(|e| RunMatrixError::StatMooncake(e))?;
+    let check_js = stat_mooncake(workdir, source, MoonCommand::Check(Backend::Js), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
 
-    let build_wasm = stat_mooncake(workdir, source, MoonCommand::Build(Backend::Wasm))
-        .map_err(|e| RunMatrixError::StatMooncake(e))?;
-    let build_wasm_gc = stat_mooncake(workdir, source, MoonCommand::Build(Backend::WasmGC))
+    let build_wasm = stat_mooncake(workdir, source, MoonCommand::Build(Backend::Wasm), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
-    let build_js = stat_mooncake(workdir, source, MoonCommand::Build(Backend::Js))
+    let build_wasm_gc = stat_mooncake(
+        workdir,
+        source,
+        MoonCommand::Build(Backend::WasmGC),
+        options,
+    )
+    .map_err(|e| RunMatrixError::StatMooncake(e))?;
+    let build_js = stat_mooncake(workdir, source, MoonCommand::Build(Backend::Js), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
 
-    let test_wasm = stat_mooncake(workdir, source, MoonCommand::Test(Backend::Wasm))
+    let test_wasm = stat_mooncake(workdir, source, MoonCommand::Test(Backend::Wasm), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
-    let test_wasm_gc = stat_mooncake(workdir, source, MoonCommand::Test(Backend::WasmGC))
+    let test_wasm_gc = stat_mooncake(workdir, source, MoonCommand::Test(Backend::WasmGC), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
-    let test_js = stat_mooncake(workdir, source, MoonCommand::Test(Backend::Js))
+    let test_js = stat_mooncake(workdir, source, MoonCommand::Test(Backend::Js), options)
         .map_err(|e| RunMatrixError::StatMooncake(e))?;
 
     Ok(CBT {
@@ -316,11 +801,33 @@ enum StatErrorKind {
 
     #[error("failed on build")]
     BuildError(#[from] BuildError),
+
+    #[error("build sandbox unavailable")]
+    Sandbox(std::io::Error),
 }
 
 fn stat(cmd: cli::StatSubcommand) -> Result<MoonBuildDashboard, StatError> {
     let run_id = std::env::var("GITHUB_ACTION_RUN_ID").unwrap_or("0".into());
     let run_number = std::env::var("GITHUB_ACTION_RUN_NUMBER").unwrap_or("0".into());
+    let mut options = BuildOptions::from(&cmd);
+    options.registries = Registries::load(cmd.registries.as_deref()).map_err(|e| StatError {
+        kind: StatErrorKind::GetMooncakeSourcesError(GetMooncakeSourcesError {
+            kind: GetMooncakeSourcesErrorKind::MooncakesIO(e),
+        }),
+    })?;
+    if options.sandbox.is_some() {
+        sandbox::check_available().map_err(|e| StatError {
+            kind: StatErrorKind::Sandbox(e),
+        })?;
+    }
+    // incremental runs reuse the recorded results, flakiness covers the recent ones
+    let previous = history::load_runs(Path::new(history::DEFAULT_DATA_FILE)).unwrap_or_else(|e| {
+        eprintln!(
+            "failed to load history, flakiness only covers this run and nothing is carried over: {:?}",
+            e
+        );
+        vec![]
+    });
 
     if !cmd.skip_install {
         install_stable_release().map_err(|e| StatError {
@@ -343,14 +850,18 @@ fn stat(cmd: cli::StatSubcommand) -> Result<MoonBuildDashboard, StatError> {
         moon_version,
         moonc_version,
     };
+    if cmd.incremental {
+        options.cache = Some(ResultCache::from_runs(&previous, &stable_toolchain_version));
+    }
 
-    let mooncake_sources = get_mooncake_sources(&cmd).map_err(|e| StatError {
-        kind: StatErrorKind::GetMooncakeSourcesError(e),
-    })?;
+    let mooncake_sources =
+        get_mooncake_sources(&cmd, &options.registries).map_err(|e| StatError {
+            kind: StatErrorKind::GetMooncakeSourcesError(e),
+        })?;
     let mut stable_release_data = vec![];
 
     for source in mooncake_sources {
-        let build_state = build(&source).map_err(|e| StatError {
+        let build_state = build(&source, &options).map_err(|e| StatError {
             kind: StatErrorKind::BuildError(e),
         })?;
         stable_release_data.push(build_state);
@@ -377,20 +888,29 @@ fn stat(cmd: cli::StatSubcommand) -> Result<MoonBuildDashboard, StatError> {
         moon_version,
         moonc_version,
     };
+    if cmd.incremental {
+        options.cache = Some(ResultCache::from_runs(
+            &previous,
+            &bleeding_toolchain_version,
+        ));
+    }
 
-    let mooncake_sources = get_mooncake_sources(&cmd).map_err(|e| StatError {
-        kind: StatErrorKind::GetMooncakeSourcesError(e),
-    })?;
+    let mooncake_sources =
+        get_mooncake_sources(&cmd, &options.registries).map_err(|e| StatError {
+            kind: StatErrorKind::GetMooncakeSourcesError(e),
+        })?;
     let mut bleeding_release_data = vec![];
 
     for source in mooncake_sources.iter() {
-        let build_state = build(source).map_err(|e| StatError {
+        let build_state = build(source, &options).map_err(|e| StatError {
             kind: StatErrorKind::BuildError(e),
         })?;
         bleeding_release_data.push(build_state);
     }
 
-    let result = MoonBuildDashboard {
+    let toolchain_comparison =
+        regression::compare_toolchains(&stable_release_data, &bleeding_release_data);
+    let mut result = MoonBuildDashboard {
         run_id,
         run_number,
         sources: mooncake_sources,
@@ -399,28 +919,696 @@ fn stat(cmd: cli::StatSubcommand) -> Result<MoonBuildDashboard, StatError> {
         stable_release_data,
         bleeding_toolchain_version,
         bleeding_release_data,
+        toolchain_comparison,
+        flakiness: vec![],
+        health: vec![],
     };
+    // flakiness covers the recent recorded runs plus this one
+    let recent = previous
+        .iter()
+        .rev()
+        .take(flaky::FLAKINESS_WINDOW - 1)
+        .chain(std::iter::once(&result));
+    result.flakiness = flaky::flakiness(recent);
+    let flagships = if cmd.flagships.is_empty() {
+        health::DEFAULT_FLAGSHIPS
+            .iter()
+            .map(|f| f.to_string())
+            .collect()
+    } else {
+        cmd.flagships.clone()
+    };
+    result.health = health::health_scores(&result, &flagships);
+    let regressions = regression::prioritized_regressions(&result);
+    eprint!("{}", regression::render_regressions(&result, &regressions));
     Ok(result)
 }
 
+#[derive(Debug, thiserror::Error)]
+#[error("report error")]
+struct ReportError {
+    #[source]
+    kind: ReportErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ReportErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+}
+
+fn report(cmd: cli::ReportSubcommand) -> Result<(), ReportError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    let runs = history::load_runs(&data).map_err(|e| ReportError {
+        kind: ReportErrorKind::History(e),
+    })?;
+    let run = history::select_run(&runs, cmd.run_id.as_deref()).map_err(|e| ReportError {
+        kind: ReportErrorKind::History(e),
+    })?;
+    let content = match cmd.format {
+        cli::ReportFormat::Markdown => report::render_markdown(run, cmd.slowest),
+        cli::ReportFormat::Html => {
+            // the selected run plus the runs recorded before it
+            let end = runs
+                .iter()
+                .rposition(|r| std::ptr::eq(r, run))
+                .map_or(runs.len(), |i| i + 1);
+            let options = html::HtmlOptions {
+                base_path: cmd.base_path.clone(),
+                history: cmd.history,
+            };
+            html::render_html(&runs[..end], &options)
+        }
+    };
+
+    match &cmd.output {
+        Some(output) => std::fs::write(output, &content).map_err(|e| ReportError {
+            kind: ReportErrorKind::IOError(e),
+        })?,
+        None => print!("{}", content),
+    }
+
+    if cmd.github_step_summary {
+        match std::env::var("GITHUB_STEP_SUMMARY") {
+            Ok(summary) => {
+                let mut fp = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(summary)
+                    .map_err(|e| ReportError {
+                        kind: ReportErrorKind::IOError(e),
+                    })?;
+                fp.write_all(content.as_bytes()).map_err(|e| ReportError {
+                    kind: ReportErrorKind::IOError(e),
+                })?;
+            }
+            Err(_) => eprintln!("GITHUB_STEP_SUMMARY is not set, skipping step summary"),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("export metrics error")]
+struct ExportMetricsError {
+    #[source]
+    kind: ExportMetricsErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ExportMetricsErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+    #[error("failed on metrics")]
+    Metrics(#[from] metrics::MetricsError),
+}
+
+fn export_metrics(cmd: cli::MetricsSubcommand) -> Result<(), ExportMetricsError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    if let Some(addr) = &cmd.listen {
+        return metrics::serve(addr, &data).map_err(|e| ExportMetricsError {
+            kind: ExportMetricsErrorKind::Metrics(e),
+        });
+    }
+
+    let runs = history::load_runs(&data).map_err(|e| ExportMetricsError {
+        kind: ExportMetricsErrorKind::History(e),
+    })?;
+    let run = history::select_run(&runs, None).map_err(|e| ExportMetricsError {
+        kind: ExportMetricsErrorKind::History(e),
+    })?;
+    let content = metrics::render_openmetrics(run);
+    match &cmd.output {
+        Some(output) => {
+            // write then rename, so the textfile collector never reads a partial file
+            let tmp = output.with_extension("prom.tmp");
+            std::fs::write(&tmp, &content)
+                .and_then(|_| std::fs::rename(&tmp, output))
+                .map_err(|e| ExportMetricsError {
+                    kind: ExportMetricsErrorKind::IOError(e),
+                })?;
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("badges error")]
+struct BadgesError {
+    #[source]
+    kind: BadgesErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum BadgesErrorKind {
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+    #[error("failed on badge")]
+    Badge(#[from] badge::BadgeError),
+}
+
+fn badges(cmd: cli::BadgesSubcommand) -> Result<(), BadgesError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    let runs = history::load_runs(&data).map_err(|e| BadgesError {
+        kind: BadgesErrorKind::History(e),
+    })?;
+    let run = history::select_run(&runs, None).map_err(|e| BadgesError {
+        kind: BadgesErrorKind::History(e),
+    })?;
+    let count = badge::write_badges(run, &cmd.output_dir).map_err(|e| BadgesError {
+        kind: BadgesErrorKind::Badge(e),
+    })?;
+    eprintln!("wrote {} badges to {}", count, cmd.output_dir.display());
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("diff error")]
+struct DiffError {
+    #[source]
+    kind: DiffErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DiffErrorKind {
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+    #[error("serde")]
+    Serde(#[from] serde_json::Error),
+}
+
+fn diff(cmd: cli::DiffSubcommand) -> Result<(), DiffError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    let runs = history::load_runs(&data).map_err(|e| DiffError {
+        kind: DiffErrorKind::History(e),
+    })?;
+    let from = history::select_run(&runs, Some(&cmd.from)).map_err(|e| DiffError {
+        kind: DiffErrorKind::History(e),
+    })?;
+    let to = history::select_run(&runs, Some(&cmd.to)).map_err(|e| DiffError {
+        kind: DiffErrorKind::History(e),
+    })?;
+    let run_diff = diff::diff_runs(from, to);
+    if cmd.json {
+        let json = serde_json::to_string_pretty(&run_diff).map_err(|e| DiffError {
+            kind: DiffErrorKind::Serde(e),
+        })?;
+        println!("{}", json);
+    } else {
+        print!("{}", diff::render_table(&run_diff, cmd.all));
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("perf error")]
+struct PerfError {
+    #[source]
+    kind: PerfErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum PerfErrorKind {
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+    #[error("serde")]
+    Serde(#[from] serde_json::Error),
+}
+
+fn perf(cmd: cli::PerfSubcommand) -> Result<(), PerfError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    let runs = history::load_runs(&data).map_err(|e| PerfError {
+        kind: PerfErrorKind::History(e),
+    })?;
+    let options = perf::PerfOptions {
+        window: cmd.window,
+        threshold: cmd.threshold / 100.0,
+        min_delta_ms: cmd.min_delta_ms,
+    };
+    let changes = perf::detect_changes(&runs, &options);
+    if cmd.json {
+        let json = serde_json::to_string_pretty(&changes).map_err(|e| PerfError {
+            kind: PerfErrorKind::Serde(e),
+        })?;
+        println!("{}", json);
+    } else {
+        print!("{}", perf::render_changes(&changes));
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("show history error")]
+struct ShowHistoryError {
+    #[source]
+    kind: ShowHistoryErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ShowHistoryErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+    #[error("serde")]
+    Serde(#[from] serde_json::Error),
+}
+
+fn show_history(cmd: cli::HistorySubcommand) -> Result<(), ShowHistoryError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    let runs = history::load_runs(&data).map_err(|e| ShowHistoryError {
+        kind: ShowHistoryErrorKind::History(e),
+    })?;
+    let mut streaks = streak::failure_streaks(&runs);
+    if let Some(source) = &cmd.source {
+        streaks.retain(|s| s.source.contains(source.as_str()));
+    }
+
+    if cmd.json || cmd.output.is_some() {
+        let json = serde_json::to_string_pretty(&streaks).map_err(|e| ShowHistoryError {
+            kind: ShowHistoryErrorKind::Serde(e),
+        })?;
+        match &cmd.output {
+            Some(output) => std::fs::write(output, json).map_err(|e| ShowHistoryError {
+                kind: ShowHistoryErrorKind::IOError(e),
+            })?,
+            None => println!("{}", json),
+        }
+    } else {
+        print!("{}", streak::render_streaks(&streaks));
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("bisect toolchain error")]
+struct BisectToolchainError {
+    #[source]
+    kind: BisectToolchainErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum BisectToolchainErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("failed to get mooncake sources")]
+    GetMooncakeSources(#[from] GetMooncakeSourcesError),
+    #[error("invalid source: {0}")]
+    InvalidSource(String),
+    #[error("failed to fetch source")]
+    Build(#[from] BuildError),
+    #[error("failed to run matrix")]
+    RunMatrix(#[from] RunMatrixError),
+    #[error("failed to stat mooncake")]
+    StatMooncake(#[from] StatMooncakeError),
+    #[error("moon ops error")]
+    MoonOps(#[from] MoonOpsError),
+}
+
+fn bisect_toolchain(cmd: cli::BisectToolchainSubcommand) -> Result<(), BisectToolchainError> {
+    let mut toolchains = vec![];
+    let entries = std::fs::read_dir(&cmd.toolchains).map_err(|e| BisectToolchainError {
+        kind: BisectToolchainErrorKind::IOError(e),
+    })?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| BisectToolchainError {
+                kind: BisectToolchainErrorKind::IOError(e),
+            })?
+            .path();
+        if path.join("bin").join("moon").exists() {
+            // ordered by release, since directory names need not sort like versions
+            let (moon, moonc) =
+                util::get_toolchain_version_at(&path).map_err(|e| BisectToolchainError {
+                    kind: BisectToolchainErrorKind::MoonOps(e),
+                })?;
+            toolchains.push((
+                bisect::version_key(&moonc),
+                bisect::version_key(&moon),
+                path,
+            ));
+        }
+    }
+    toolchains.sort();
+    let toolchains: Vec<PathBuf> = toolchains.into_iter().map(|(_, _, path)| path).collect();
+
+    let registry_error = |e| BisectToolchainError {
+        kind: BisectToolchainErrorKind::GetMooncakeSources(GetMooncakeSourcesError {
+            kind: GetMooncakeSourcesErrorKind::MooncakesIO(e),
+        }),
+    };
+    let registries = Registries::load(cmd.registries.as_deref()).map_err(registry_error)?;
+    let mut dbs = RegistryDBs::new();
+    for registry in registries.all() {
+        dbs.insert(
+            registry.name().to_string(),
+            registry.get_all().map_err(registry_error)?,
+        );
+    }
+    let source = parse_source_spec(&cmd.source, 0, &dbs)
+        .map_err(|e| BisectToolchainError {
+            kind: BisectToolchainErrorKind::GetMooncakeSources(e),
+        })?
+        .into_iter()
+        .next()
+        .ok_or_else(|| BisectToolchainError {
+            kind: BisectToolchainErrorKind::InvalidSource(cmd.source.clone()),
+        })?;
+    let tmp = tempfile::tempdir().map_err(|e| BisectToolchainError {
+        kind: BisectToolchainErrorKind::IOError(e),
+    })?;
+    let workdir =
+        fetch_source(&source, &registries, tmp.path()).map_err(|e| BisectToolchainError {
+            kind: BisectToolchainErrorKind::Build(e),
+        })?;
+
+    let outcome = bisect::bisect(toolchains.len(), |i| {
+        let options = BuildOptions {
+            toolchain: Some(toolchains[i].clone()),
+            ..Default::default()
+        };
+        eprintln!("{}", format!("TRY {}", toolchains[i].display()).bold());
+        let good = match cmd.cell {
+            Some(cell) => stat_mooncake(&workdir, &source, cell, &options)
+                .map_err(|e| BisectToolchainError {
+                    kind: BisectToolchainErrorKind::StatMooncake(e),
+                })?
+                .status
+                .is_success(),
+            None => {
+                let cbt =
+                    run_matrix(&workdir, &source, &options).map_err(|e| BisectToolchainError {
+                        kind: BisectToolchainErrorKind::RunMatrix(e),
+                    })?;
+                MoonCommand::all()
+                    .into_iter()
+                    .all(|cmd| cbt.get(cmd).status.is_success())
+            }
+        };
+        eprintln!("{}", if good { "good".green() } else { "bad".red() });
+        Ok(good)
+    })?;
+
+    let describe = |i: usize| -> Result<String, BisectToolchainError> {
+        let (moon, moonc) =
+            util::get_toolchain_version_at(&toolchains[i]).map_err(|e| BisectToolchainError {
+                kind: BisectToolchainErrorKind::MoonOps(e),
+            })?;
+        Ok(format!(
+            "{} ({} / {})",
+            toolchains[i].display(),
+            moon,
+            moonc
+        ))
+    };
+    match outcome {
+        bisect::BisectOutcome::Found {
+            last_good,
+            first_bad,
+        } => {
+            println!("last good: {}", describe(last_good)?);
+            println!("{} {}", "first bad:".red().bold(), describe(first_bad)?);
+        }
+        bisect::BisectOutcome::FirstIsBad => {
+            println!("already broken with the oldest toolchain {}", describe(0)?)
+        }
+        bisect::BisectOutcome::LastIsGood => println!(
+            "still passing with the newest toolchain {}",
+            describe(toolchains.len() - 1)?
+        ),
+        bisect::BisectOutcome::Empty => {
+            println!("need at least two toolchains to bisect")
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("bisect source error")]
+struct BisectSourceError {
+    #[source]
+    kind: BisectSourceErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum BisectSourceErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("git error")]
+    Git(#[from] git::GitOpsError),
+    #[error("failed to stat mooncake")]
+    StatMooncake(#[from] StatMooncakeError),
+}
+
+fn bisect_source(cmd: cli::BisectSourceSubcommand) -> Result<(), BisectSourceError> {
+    let tmp = tempfile::tempdir().map_err(|e| BisectSourceError {
+        kind: BisectSourceErrorKind::IOError(e),
+    })?;
+    git::git_clone_to(&cmd.url, tmp.path(), "test").map_err(|e| BisectSourceError {
+        kind: BisectSourceErrorKind::Git(e),
+    })?;
+    let workdir = tmp.path().join("test");
+    let source = MooncakeSource::Git {
+        url: cmd.url.clone(),
+        rev: vec![],
+        index: 0,
+    };
+    let options = BuildOptions {
+        toolchain: cmd.toolchain.clone(),
+        ..Default::default()
+    };
+    let is_good = |rev: &str| -> Result<bool, BisectSourceError> {
+        eprintln!("{}", format!("TRY {}", rev).bold());
+        let good = stat_mooncake(&workdir, &source, cmd.cell, &options)
+            .map_err(|e| BisectSourceError {
+                kind: BisectSourceErrorKind::StatMooncake(e),
+            })?
+            .status
+            .is_success();
+        eprintln!("{}", if good { "good".green() } else { "bad".red() });
+        Ok(good)
+    };
+    let describe = |rev: &str| -> Result<String, BisectSourceError> {
+        let info = git::get_commit_info(&workdir, rev).map_err(|e| BisectSourceError {
+            kind: BisectSourceErrorKind::Git(e),
+        })?;
+        Ok(format!(
+            "{}\n  author: {}\n  date:   {}\n  {}",
+            info.hash, info.author, info.date, info.subject
+        ))
+    };
+
+    // resolve both ends up front, relative revisions like `HEAD~10` move with checkouts
+    let good_rev = git::git_rev_parse(&workdir, &cmd.good_rev).map_err(|e| BisectSourceError {
+        kind: BisectSourceErrorKind::Git(e),
+    })?;
+    let bad_rev = git::git_rev_parse(&workdir, &cmd.bad_rev).map_err(|e| BisectSourceError {
+        kind: BisectSourceErrorKind::Git(e),
+    })?;
+
+    // git bisect trusts the given ends, so check them before searching between them
+    for (rev, expected) in [(&good_rev, true), (&bad_rev, false)] {
+        git::git_checkout(&workdir, rev).map_err(|e| BisectSourceError {
+            kind: BisectSourceErrorKind::Git(e),
+        })?;
+        if is_good(rev)? != expected {
+            println!(
+                "{} is {} {}, nothing to bisect",
+                if expected {
+                    &cmd.good_rev
+                } else {
+                    &cmd.bad_rev
+                },
+                if expected { "failing" } else { "passing" },
+                cmd.cell
+            );
+            return Ok(());
+        }
+    }
+
+    git::git_bisect_start(&workdir, &bad_rev, &good_rev).map_err(|e| BisectSourceError {
+        kind: BisectSourceErrorKind::Git(e),
+    })?;
+    let first_bad = loop {
+        let rev = git::get_git_short_hash(&workdir).map_err(|e| BisectSourceError {
+            kind: BisectSourceErrorKind::Git(e),
+        })?;
+        let good = is_good(&rev)?;
+        if let Some(first_bad) =
+            git::git_bisect_mark(&workdir, good).map_err(|e| BisectSourceError {
+                kind: BisectSourceErrorKind::Git(e),
+            })?
+        {
+            break first_bad;
+        }
+    };
+    let _ = git::git_bisect_reset(&workdir);
+
+    println!(
+        "{} {} {}",
+        "first bad commit for".red().bold(),
+        cmd.cell,
+        describe(&first_bad)?
+    );
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("health error")]
+struct HealthError {
+    #[source]
+    kind: HealthErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum HealthErrorKind {
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+    #[error("serde")]
+    Serde(#[from] serde_json::Error),
+}
+
+fn show_health(cmd: cli::HealthSubcommand) -> Result<(), HealthError> {
+    let data = cmd
+        .data
+        .unwrap_or_else(|| history::DEFAULT_DATA_FILE.into());
+    let runs = history::load_runs(&data).map_err(|e| HealthError {
+        kind: HealthErrorKind::History(e),
+    })?;
+    let runs = &runs[runs.len().saturating_sub(cmd.last)..];
+    if cmd.json {
+        let health: Vec<serde_json::Value> = runs
+            .iter()
+            .map(|run| {
+                serde_json::json!({
+                    "run_id": run.run_id,
+                    "run_number": run.run_number,
+                    "start_time": run.start_time,
+                    "health": health::run_health(run),
+                })
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&health).map_err(|e| HealthError {
+            kind: HealthErrorKind::Serde(e),
+        })?;
+        println!("{}", json);
+    } else {
+        print!("{}", health::render_health(runs));
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("gen list error")]
+struct GenListError {
+    #[source]
+    kind: GenListErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum GenListErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("failed to load the registry")]
+    GetMooncakeSources(#[from] GetMooncakeSourcesError),
+}
+
+fn gen_list(cmd: cli::GenListSubcommand) -> Result<(), GenListError> {
+    let db = load_registry(&mooncakesio::PublicRegistry, cmd.rules.as_deref()).map_err(|e| {
+        GenListError {
+            kind: GenListErrorKind::GetMooncakeSources(e),
+        }
+    })?;
+    let filter = genlist::GenListFilter {
+        owners: cmd.owners,
+        keywords: cmd.keywords,
+        updated_since: cmd.updated_since,
+        versions: cmd.versions,
+    };
+    let entries = genlist::generate(&db, &filter);
+    let existing = match std::fs::read_to_string(&cmd.file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(GenListError {
+                kind: GenListErrorKind::IOError(e),
+            })
+        }
+    };
+    let update = genlist::update_manifest(&existing, &entries);
+    if !cmd.dry_run {
+        std::fs::write(&cmd.file, &update.content).map_err(|e| GenListError {
+            kind: GenListErrorKind::IOError(e),
+        })?;
+    }
+    println!(
+        "{} packages generated, {} added, {} removed, {} excluded by rules",
+        entries.len(),
+        update.added.len(),
+        update.removed.len(),
+        db.excluded().len()
+    );
+    for name in &update.added {
+        println!("  {} {}", "+".green().bold(), name);
+    }
+    for name in &update.removed {
+        println!("  {} {}", "-".red().bold(), name);
+    }
+    Ok(())
+}
+
 fn main0() -> anyhow::Result<()> {
     let cli = cli::MoonBuildDashBoardCli::parse();
-    let res = match cli.subcommand {
-        cli::MoonBuildDashBoardSubcommands::Stat(cmd) => stat(cmd),
-    };
-    match res {
-        Ok(dashboard) => {
+    match cli.subcommand {
+        cli::MoonBuildDashBoardSubcommands::Stat(cmd) => {
+            let dashboard = stat(cmd)?;
+            if let Some(dir) = Path::new(history::DEFAULT_DATA_FILE).parent() {
+                std::fs::create_dir_all(dir)?;
+            }
             let fp = std::fs::OpenOptions::new()
                 .create(true)
                 .append(true)
-                .open("webapp/public/data.jsonl")?;
+                .open(history::DEFAULT_DATA_FILE)?;
             let mut writer = std::io::BufWriter::new(fp);
             writeln!(writer, "{}", serde_json::to_string(&dashboard)?)?;
             writer.flush()?;
-            Ok(())
+
+            let runs = history::load_runs(Path::new(history::DEFAULT_DATA_FILE))?;
+            let streaks = streak::failure_streaks(&runs);
+            std::fs::write(
+                streak::DEFAULT_STREAKS_FILE,
+                serde_json::to_string_pretty(&streaks)?,
+            )?;
         }
-        Err(e) => Err(anyhow::anyhow!(e)),
+        cli::MoonBuildDashBoardSubcommands::Report(cmd) => report(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::Metrics(cmd) => export_metrics(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::Badges(cmd) => badges(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::Diff(cmd) => diff(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::Perf(cmd) => perf(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::History(cmd) => show_history(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::BisectToolchain(cmd) => bisect_toolchain(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::BisectSource(cmd) => bisect_source(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::Health(cmd) => show_health(cmd)?,
+        cli::MoonBuildDashBoardSubcommands::GenList(cmd) => gen_list(cmd)?,
     }
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
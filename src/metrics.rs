@@ -0,0 +1,313 @@
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+};
+
+use crate::{
+    dashboard::{Cell, MoonBuildDashboard, ToolChainLabel},
+    health, history,
+};
+
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+#[derive(Debug, thiserror::Error)]
+#[error("metrics error")]
+pub struct MetricsError {
+    #[source]
+    kind: MetricsErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetricsErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("failed on history")]
+    History(#[from] history::HistoryError),
+}
+
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn labels(pairs: &[(&str, &str)]) -> String {
+    let pairs: Vec<String> = pairs
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Labels of a cell, its source by slug so that same-named packages of
+/// different registries get series of their own.
+fn cell_labels(cell: &Cell) -> String {
+    labels(&[
+        ("source", &cell.source.slug()),
+        ("version", cell.version),
+        ("toolchain", &cell.label.to_string()),
+        ("command", cell.cmd.name()),
+        ("backend", cell.cmd.backend().to_flag()),
+    ])
+}
+
+fn family(out: &mut String, name: &str, help: &str, unit: Option<&str>) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    if let Some(unit) = unit {
+        let _ = writeln!(out, "# UNIT {} {}", name, unit);
+    }
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+}
+
+/// Renders a run in the OpenMetrics text format, terminated by `# EOF`.
+pub fn render_openmetrics(run: &MoonBuildDashboard) -> String {
+    let mut out = String::new();
+
+    family(
+        &mut out,
+        "moon_dashboard_run_info",
+        "Run the metrics were taken from.",
+        None,
+    );
+    let _ = writeln!(
+        out,
+        "moon_dashboard_run_info{} 1",
+        labels(&[("run_id", &run.run_id), ("run_number", &run.run_number)])
+    );
+
+    family(
+        &mut out,
+        "moon_dashboard_toolchain_info",
+        "Toolchain versions used by the run.",
+        None,
+    );
+    for label in ToolChainLabel::all() {
+        let version = run.toolchain_version(label);
+        let _ = writeln!(
+            out,
+            "moon_dashboard_toolchain_info{} 1",
+            labels(&[
+                ("toolchain", &label.to_string()),
+                ("moon_version", &version.moon_version),
+                ("moonc_version", &version.moonc_version),
+            ])
+        );
+    }
+
+    family(
+        &mut out,
+        "moon_dashboard_cell_passed",
+        "Whether a matrix cell passed (1) or failed (0).",
+        None,
+    );
+    // a source listed twice would repeat a series, which OpenMetrics rejects
+    let mut seen = HashSet::new();
+    for label in ToolChainLabel::all() {
+        for cell in run.cells(label) {
+            let series = cell_labels(&cell);
+            if !seen.insert(series.clone()) {
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "moon_dashboard_cell_passed{} {}",
+                series,
+                u8::from(cell.result.status.is_success())
+            );
+        }
+    }
+
+    family(
+        &mut out,
+        "moon_dashboard_cell_duration_seconds",
+        "Duration of successful matrix cells.",
+        Some("seconds"),
+    );
+    let mut seen = HashSet::new();
+    for label in ToolChainLabel::all() {
+        for cell in run.cells(label) {
+            let series = cell_labels(&cell);
+            if !cell.result.status.is_success() || !seen.insert(series.clone()) {
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "moon_dashboard_cell_duration_seconds{} {}",
+                series,
+                cell.result.elapsed as f64 / 1000.0
+            );
+        }
+    }
+
+    family(
+        &mut out,
+        "moon_dashboard_pass_ratio",
+        "Fraction of matrix cells passing per toolchain.",
+        Some("ratio"),
+    );
+    for label in ToolChainLabel::all() {
+        let cells = run.cells(label);
+        let passed = cells
+            .iter()
+            .filter(|c| c.result.status.is_success())
+            .count();
+        let ratio = if cells.is_empty() {
+            0.0
+        } else {
+            passed as f64 / cells.len() as f64
+        };
+        let _ = writeln!(
+            out,
+            "moon_dashboard_pass_ratio{} {}",
+            labels(&[("toolchain", &label.to_string())]),
+            ratio
+        );
+    }
+
+    let health = health::run_health(run);
+    family(
+        &mut out,
+        "moon_dashboard_health_score_ratio",
+        "Fraction of passing cells per toolchain with flagship packages weighted up.",
+        Some("ratio"),
+    );
+    for h in &health {
+        let _ = writeln!(
+            out,
+            "moon_dashboard_health_score_ratio{} {}",
+            labels(&[("toolchain", &h.toolchain.to_string())]),
+            h.score / 100.0
+        );
+    }
+    family(
+        &mut out,
+        "moon_dashboard_registry_packages_building",
+        "Registry packages with at least one version passing a build cell.",
+        None,
+    );
+    for h in &health {
+        let _ = writeln!(
+            out,
+            "moon_dashboard_registry_packages_building{} {}",
+            labels(&[("toolchain", &h.toolchain.to_string())]),
+            h.registry_building
+        );
+    }
+
+    let _ = writeln!(out, "# EOF");
+    out
+}
+
+fn render_latest(data: &Path) -> Result<String, MetricsError> {
+    let runs = history::load_runs(data).map_err(|e| MetricsError {
+        kind: MetricsErrorKind::History(e),
+    })?;
+    let run = history::select_run(&runs, None).map_err(|e| MetricsError {
+        kind: MetricsErrorKind::History(e),
+    })?;
+    Ok(render_openmetrics(run))
+}
+
+fn respond(mut stream: TcpStream, data: &Path) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, content_type, body) = if path != "/metrics" {
+        ("404 Not Found", "text/plain", "not found\n".to_string())
+    } else {
+        match render_latest(data) {
+            Ok(body) => ("200 OK", CONTENT_TYPE, body),
+            Err(e) => (
+                "500 Internal Server Error",
+                "text/plain",
+                format!("{:?}\n", e),
+            ),
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Serves `/metrics` for the latest run in `data`, re-reading the file on every scrape.
+pub fn serve(addr: &str, data: &Path) -> Result<(), MetricsError> {
+    let listener = TcpListener::bind(addr).map_err(|e| MetricsError {
+        kind: MetricsErrorKind::IOError(e),
+    })?;
+    eprintln!("serving metrics on http://{}/metrics", addr);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = respond(stream, data) {
+            eprintln!("failed to respond: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn openmetrics_output_is_terminated() {
+    use crate::dashboard::{test_build, test_dashboard, Status};
+
+    assert_eq!(escape_label("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+
+    // only stable was built, bleeding has no cells at all
+    let mut run = test_dashboard("1", &["a/lib"]);
+    let mut state = test_build(0, "0.1.0", Status::Success);
+    state.cbts[0].as_mut().unwrap().build.js.elapsed = 1500;
+    run.stable_release_data = vec![state];
+    let text = render_openmetrics(&run);
+    assert!(text.ends_with("# EOF\n"));
+    assert!(text.contains(
+        r#"moon_dashboard_cell_duration_seconds{source="a-lib",version="0.1.0",toolchain="stable",command="build",backend="js"} 1.5"#
+    ));
+    assert!(text.contains(r#"moon_dashboard_pass_ratio{toolchain="bleeding"} 0"#));
+    assert!(text.contains(r#"moon_dashboard_health_score_ratio{toolchain="stable"} 1"#));
+}
+
+#[test]
+fn openmetrics_series_are_unique() {
+    use crate::dashboard::{test_build, test_dashboard, MooncakeSource, Status};
+
+    // a/lib from the public registry, listed twice, and from a private one
+    let mut run = test_dashboard("1", &["a/lib", "a/lib", "a/lib"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[2] {
+        *registry = Some("acme".to_string());
+    }
+    run.stable_release_data = (0..3)
+        .map(|i| test_build(i, "0.1.0", Status::Success))
+        .collect();
+    let text = render_openmetrics(&run);
+
+    let mut series = HashSet::new();
+    for line in text.lines().filter(|l| !l.starts_with('#')) {
+        let (name_and_labels, _) = line.rsplit_once(' ').unwrap();
+        assert!(series.insert(name_and_labels), "duplicate series {}", line);
+    }
+    let passed = |source: &str| {
+        series
+            .iter()
+            .filter(|s| {
+                s.starts_with("moon_dashboard_cell_passed{")
+                    && s.contains(&format!("source=\"{}\"", source))
+            })
+            .count()
+    };
+    assert_eq!(passed("a-lib"), 9);
+    assert_eq!(passed("acme-a-lib"), 9);
+}
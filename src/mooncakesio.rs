@@ -1,12 +1,23 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    dashboard::IntegrityViolation,
+    integrity,
+    rules::{Exclusion, RegistryRules},
+};
+
 const BASE_URL: &str = "https://moonbitlang-mooncakes.s3.us-west-2.amazonaws.com/user";
 
+/// Name of the public registry, used by sources that name no other.
+pub const PUBLIC_REGISTRY: &str = "mooncakes.io";
+/// Registry configuration read when present and no other is given.
+pub const DEFAULT_REGISTRIES_FILE: &str = "registries.json";
+
 #[derive(Debug, thiserror::Error)]
 pub enum MooncakesIOError {
     #[error("io error")]
@@ -19,35 +30,8 @@ pub enum MooncakesIOError {
     Serde(#[from] serde_json::Error),
     #[error("walkdir")]
     WalkDir(#[from] walkdir::Error),
-}
-
-pub fn download_to(name: &str, version: &str, dst: &Path) -> Result<(), MooncakesIOError> {
-    let version_enc = form_urlencoded::Serializer::new(String::new())
-        .append_key_only(version)
-        .finish();
-    let url = format!("{}/{}/{}.zip", BASE_URL, name, version_enc);
-    let output_zip = format!("{}.zip", dst.join(version).display());
-    let output = std::process::Command::new("curl")
-        .arg("-o")
-        .arg(&output_zip)
-        .arg(&url)
-        .output()
-        .map_err(|e| MooncakesIOError::IOError(e))?;
-    if !output.status.success() {
-        return Err(MooncakesIOError::ReturnNonZero(output.status));
-    }
-
-    let output = std::process::Command::new("unzip")
-        .arg(&output_zip)
-        .arg("-d")
-        .arg(dst.join(version))
-        .output()
-        .map_err(|e| MooncakesIOError::IOError(e))?;
-    if !output.status.success() {
-        return Err(MooncakesIOError::ReturnNonZero(output.status));
-    }
-
-    Ok(())
+    #[error("broken package: {0}")]
+    Integrity(IntegrityViolation),
 }
 
 pub fn home() -> PathBuf {
@@ -71,6 +55,189 @@ pub fn index() -> PathBuf {
     home().join("registry").join("index")
 }
 
+fn encode_version(version: &str) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .append_key_only(version)
+        .finish()
+}
+
+/// Where a registry keeps its index and package archives.
+pub trait Registry: std::fmt::Debug {
+    fn name(&self) -> &str;
+
+    /// Local checkout of the registry index, one `user/<owner>/<package>.index` file per package.
+    fn index(&self) -> PathBuf;
+
+    /// URL of the zip archive of one version of a package.
+    fn download_url(&self, name: &str, version: &str) -> String;
+
+    /// Downloads and unpacks one version of a package into `dst/<version>`, verifying
+    /// the archive and the package against the index entry, see [`integrity`].
+    fn download_to(&self, name: &str, version: &str, dst: &Path) -> Result<(), MooncakesIOError> {
+        let url = self.download_url(name, version);
+        let output_zip = format!("{}.zip", dst.join(version).display());
+        let output = std::process::Command::new("curl")
+            .arg("-f")
+            .arg("--max-filesize")
+            .arg(integrity::MAX_ARCHIVE_SIZE.to_string())
+            .arg("-o")
+            .arg(&output_zip)
+            .arg(&url)
+            .output()
+            .map_err(|e| MooncakesIOError::IOError(e))?;
+        // curl stops a download going over --max-filesize with exit code 63
+        if output.status.code() == Some(63) {
+            return Err(MooncakesIOError::Integrity(
+                IntegrityViolation::ArchiveTooLarge {
+                    limit: integrity::MAX_ARCHIVE_SIZE,
+                },
+            ));
+        }
+        if !output.status.success() {
+            return Err(MooncakesIOError::ReturnNonZero(output.status));
+        }
+        let output = std::process::Command::new("unzip")
+            .arg("-l")
+            .arg(&output_zip)
+            .output()
+            .map_err(MooncakesIOError::IOError)?;
+        if !output.status.success() {
+            return Err(MooncakesIOError::ReturnNonZero(output.status));
+        }
+        let listing = String::from_utf8(output.stdout).map_err(MooncakesIOError::FromUtf8)?;
+        if let Some(violation) = integrity::check_entries(&integrity::parse_listing(&listing)) {
+            return Err(MooncakesIOError::Integrity(violation));
+        }
+        // unzip would create the links, which may point anywhere
+        let output = std::process::Command::new("zipinfo")
+            .arg(&output_zip)
+            .output()
+            .map_err(MooncakesIOError::IOError)?;
+        if !output.status.success() {
+            return Err(MooncakesIOError::ReturnNonZero(output.status));
+        }
+        let zipinfo = String::from_utf8(output.stdout).map_err(MooncakesIOError::FromUtf8)?;
+        if let Some(violation) = integrity::find_symlink(&zipinfo) {
+            return Err(MooncakesIOError::Integrity(violation));
+        }
+
+        let output = std::process::Command::new("unzip")
+            .arg(&output_zip)
+            .arg("-d")
+            .arg(dst.join(version))
+            .output()
+            .map_err(|e| MooncakesIOError::IOError(e))?;
+        if !output.status.success() {
+            return Err(MooncakesIOError::ReturnNonZero(output.status));
+        }
+        if let Some(violation) = integrity::check_package(&dst.join(version), name, version) {
+            return Err(MooncakesIOError::Integrity(violation));
+        }
+
+        Ok(())
+    }
+
+    /// Reads every published version of one package from the index, oldest first.
+    fn get_package(&self, name: &str) -> Result<Vec<MooncakeInfo>, MooncakesIOError> {
+        let path = self.index().join("user").join(format!("{}.index", name));
+        let content = std::fs::read_to_string(path).map_err(MooncakesIOError::IOError)?;
+        content
+            .lines()
+            .map(|line| serde_json::from_str(line).map_err(MooncakesIOError::Serde))
+            .collect()
+    }
+
+    /// Loads every package of the index, see [`MooncakesDB::apply_rules`] for filtering.
+    fn get_all(&self) -> Result<MooncakesDB, MooncakesIOError> {
+        get_mooncakes_at(&self.index())
+    }
+}
+
+/// The public registry behind mooncakes.io, with the index `moon update` keeps in `~/.moon`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicRegistry;
+
+impl Registry for PublicRegistry {
+    fn name(&self) -> &str {
+        PUBLIC_REGISTRY
+    }
+
+    fn index(&self) -> PathBuf {
+        index()
+    }
+
+    fn download_url(&self, name: &str, version: &str) -> String {
+        format!("{}/{}/{}.zip", BASE_URL, name, encode_version(version))
+    }
+}
+
+/// A registry such as a private mirror, configured in [`DEFAULT_REGISTRIES_FILE`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomRegistry {
+    #[serde(skip)]
+    pub name: String,
+    pub index: PathBuf,
+    /// Archive URL with `{name}` and `{version}` replaced, the version URL-encoded.
+    pub download_url: String,
+}
+
+impl Registry for CustomRegistry {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn index(&self) -> PathBuf {
+        self.index.clone()
+    }
+
+    fn download_url(&self, name: &str, version: &str) -> String {
+        self.download_url
+            .replace("{name}", name)
+            .replace("{version}", &encode_version(version))
+    }
+}
+
+/// The public registry and every configured one, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Registries {
+    custom: BTreeMap<String, CustomRegistry>,
+}
+
+impl Registries {
+    /// Reads a map from registry name to [`CustomRegistry`] from `path`, falling back to the
+    /// public registry alone when no path is given and [`DEFAULT_REGISTRIES_FILE`] does not exist.
+    pub fn load(path: Option<&Path>) -> Result<Registries, MooncakesIOError> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_REGISTRIES_FILE).exists() => {
+                Path::new(DEFAULT_REGISTRIES_FILE)
+            }
+            None => return Ok(Registries::default()),
+        };
+        let content = std::fs::read_to_string(path).map_err(MooncakesIOError::IOError)?;
+        let mut custom: BTreeMap<String, CustomRegistry> =
+            serde_json::from_str(&content).map_err(MooncakesIOError::Serde)?;
+        for (name, registry) in custom.iter_mut() {
+            registry.name = name.clone();
+        }
+        Ok(Registries { custom })
+    }
+
+    /// The registry named `name`, the public one for `None`.
+    pub fn get(&self, name: Option<&str>) -> Option<&dyn Registry> {
+        match name {
+            None | Some(PUBLIC_REGISTRY) => Some(&PublicRegistry),
+            Some(name) => self.custom.get(name).map(|r| r as &dyn Registry),
+        }
+    }
+
+    /// The public registry first, then the configured ones.
+    pub fn all(&self) -> impl Iterator<Item = &dyn Registry> {
+        std::iter::once(&PublicRegistry as &dyn Registry)
+            .chain(self.custom.values().map(|r| r as &dyn Registry))
+    }
+}
+
 pub fn index_of_pkg(base: &Path, user: &str, pkg: &str) -> PathBuf {
     base.join("user")
         .join(user)
@@ -80,7 +247,8 @@ pub fn index_of_pkg(base: &Path, user: &str, pkg: &str) -> PathBuf {
 
 #[derive(Debug, Default)]
 pub struct MooncakesDB {
-    db: BTreeMap<String, Vec<String>>,
+    db: BTreeMap<String, Vec<MooncakeInfo>>,
+    excluded: BTreeMap<String, Exclusion>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -94,52 +262,157 @@ pub struct MooncakesDBError {
 pub enum MooncakesDBErrorKind {
     #[error("key not found: {key}")]
     NotFound { key: String },
+    #[error("every version of {key} is yanked")]
+    AllYanked { key: String },
 }
 
 impl MooncakesDB {
+    /// The latest version that is not yanked.
     pub fn get_latest_version(&self, name: &str) -> Result<String, MooncakesDBError> {
-        self.db
-            .get(name)
-            .map(|versions| versions.last().unwrap().to_string())
+        let versions = self.db.get(name).ok_or(MooncakesDBError {
+            kind: MooncakesDBErrorKind::NotFound {
+                key: name.to_string(),
+            },
+        })?;
+        versions
+            .iter()
+            .rev()
+            .find(|v| !v.yanked)
+            .map(|v| v.version.to_string())
             .ok_or(MooncakesDBError {
-                kind: MooncakesDBErrorKind::NotFound {
+                kind: MooncakesDBErrorKind::AllYanked {
                     key: name.to_string(),
                 },
             })
     }
 
+    pub fn get_version(&self, name: &str, version: &str) -> Option<&MooncakeInfo> {
+        self.db.get(name)?.iter().find(|v| v.version == version)
+    }
+
     pub fn contains_key(&self, name: &str) -> bool {
         self.db.contains_key(name)
     }
+
+    /// Removes the packages excluded by `rules`, remembering the rule that excluded each.
+    pub fn apply_rules(&mut self, rules: &RegistryRules) {
+        let excluded: Vec<(String, Exclusion)> = self
+            .db
+            .iter()
+            .filter_map(|(name, versions)| Some((name.clone(), rules.check(name, versions)?)))
+            .collect();
+        for (name, exclusion) in excluded {
+            self.db.remove(&name);
+            self.excluded.insert(name, exclusion);
+        }
+    }
+
+    /// Packages removed by [`MooncakesDB::apply_rules`] and the rule that removed each.
+    pub fn excluded(&self) -> &BTreeMap<String, Exclusion> {
+        &self.excluded
+    }
+
+    /// Every published version of a package, oldest first.
+    pub fn get(&self, name: &str) -> Option<&[MooncakeInfo]> {
+        self.db.get(name).map(|versions| versions.as_slice())
+    }
+
+    pub fn packages(&self) -> impl Iterator<Item = (&str, &[MooncakeInfo])> {
+        self.db
+            .iter()
+            .map(|(name, versions)| (name.as_str(), versions.as_slice()))
+    }
+
+    /// Builds the dependency graph of the registry from the latest non-yanked
+    /// version of every package.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        for (name, versions) in &self.db {
+            let Some(latest) = versions.iter().rev().find(|v| !v.yanked) else {
+                continue;
+            };
+            for dep in latest.deps.keys() {
+                graph
+                    .deps
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(dep.clone());
+                graph
+                    .rdeps
+                    .entry(dep.clone())
+                    .or_default()
+                    .insert(name.clone());
+            }
+        }
+        graph
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct MooncakeInfo {
-    version: String,
-    keywords: Option<Vec<String>>,
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    deps: BTreeMap<String, BTreeSet<String>>,
+    rdeps: BTreeMap<String, BTreeSet<String>>,
 }
 
-#[test]
-fn gen_latest_list() {
-    let db = get_all_mooncakes().unwrap();
-    for (name, versions) in db.db {
-        let latest_version = versions.last().unwrap();
-        println!("{} {}", name, latest_version);
+impl DependencyGraph {
+    /// Packages `name` depends on directly.
+    pub fn deps(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.deps
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|s| s.as_str())
+    }
+
+    /// Packages depending on `name` directly.
+    pub fn rdeps(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.rdeps
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|s| s.as_str())
     }
 }
 
-#[test]
-fn gen_latest_list_with_version() {
-    let db = get_all_mooncakes().unwrap();
-    for (name, versions) in db.db {
-        let latest_version = versions.last().unwrap();
-        println!("{} latest {}", name, latest_version);
+/// One line of a package's registry index, i.e. one published version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MooncakeInfo {
+    pub version: String,
+    pub keywords: Option<Vec<String>>,
+    /// Dependency name to its version, or to an object with a `version` field.
+    #[serde(default)]
+    pub deps: BTreeMap<String, serde_json::Value>,
+    pub repository: Option<String>,
+    pub license: Option<String>,
+    pub description: Option<String>,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub yanked: bool,
+    /// `true` or a deprecation message.
+    #[serde(default)]
+    pub deprecated: Option<serde_json::Value>,
+}
+
+impl MooncakeInfo {
+    pub fn is_deprecated(&self) -> bool {
+        match &self.deprecated {
+            Some(serde_json::Value::Bool(deprecated)) => *deprecated,
+            Some(serde_json::Value::String(message)) => !message.is_empty(),
+            _ => false,
+        }
+    }
+
+    pub fn dep_version(&self, name: &str) -> Option<&str> {
+        let dep = self.deps.get(name)?;
+        dep.as_str()
+            .or_else(|| dep.get("version").and_then(|v| v.as_str()))
     }
 }
 
-pub fn get_all_mooncakes() -> Result<MooncakesDB, MooncakesIOError> {
-    let mut db: BTreeMap<String, Vec<String>> = BTreeMap::new();
-    let dir = index().join("user");
+/// Loads every package of the registry index checked out at `index`.
+pub fn get_mooncakes_at(index: &Path) -> Result<MooncakesDB, MooncakesIOError> {
+    let mut db: BTreeMap<String, Vec<MooncakeInfo>> = BTreeMap::new();
+    let dir = index.join("user");
     let walker = walkdir::WalkDir::new(&dir).into_iter();
     for entry in walker.filter_map(|e| e.ok()).filter(|e| {
         e.path().is_file() && e.path().extension().and_then(|ext| ext.to_str()) == Some("index")
@@ -151,21 +424,120 @@ pub fn get_all_mooncakes() -> Result<MooncakesDB, MooncakesIOError> {
 
         let index_file_content =
             std::fs::read_to_string(entry.path()).map_err(|e| MooncakesIOError::IOError(e))?;
-        let mut is_mooncakes_test = false;
         let mut indexes = vec![];
         for line in index_file_content.lines() {
             let index: MooncakeInfo =
                 serde_json::from_str(line).map_err(|e| MooncakesIOError::Serde(e))?;
-            indexes.push(index.version);
-            if let Some(keywords) = &index.keywords {
-                if keywords.contains(&"mooncakes-test".to_string()) {
-                    is_mooncakes_test = true;
-                }
-            }
-        }
-        if !is_mooncakes_test {
-            db.insert(name.to_string(), indexes);
+            indexes.push(index);
         }
+        db.insert(name.to_string(), indexes);
     }
-    Ok(MooncakesDB { db })
+    Ok(MooncakesDB {
+        db,
+        excluded: BTreeMap::new(),
+    })
+}
+
+#[test]
+fn registry_dependency_graph() {
+    let tmp = tempfile::tempdir().unwrap();
+    let write = |user: &str, pkg: &str, lines: &[&str]| {
+        let path = index_of_pkg(tmp.path(), user, pkg);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, lines.join("\n")).unwrap();
+    };
+    write(
+        "moonbitlang",
+        "x",
+        &[r#"{"name":"moonbitlang/x","version":"0.4.6","license":"Apache-2.0"}"#],
+    );
+    write(
+        "a",
+        "uses-x",
+        &[
+            r#"{"name":"a/uses-x","version":"0.1.0"}"#,
+            r#"{"name":"a/uses-x","version":"0.2.0","deps":{"moonbitlang/x":"0.4.6"},"repository":"https://github.com/a/uses-x"}"#,
+        ],
+    );
+    write(
+        "b",
+        "old",
+        &[
+            r#"{"name":"b/old","version":"0.1.0","deps":{"moonbitlang/x":{"version":"0.4.0"}}}"#,
+            r#"{"name":"b/old","version":"0.2.0"}"#,
+        ],
+    );
+    // the yanked latest version does not count
+    write(
+        "c",
+        "yanked",
+        &[
+            r#"{"name":"c/yanked","version":"0.1.0","deps":{"moonbitlang/x":"0.4.6"}}"#,
+            r#"{"name":"c/yanked","version":"0.2.0","yanked":true}"#,
+        ],
+    );
+
+    let db = get_mooncakes_at(tmp.path()).unwrap();
+    let graph = db.dependency_graph();
+    assert_eq!(
+        graph.rdeps("moonbitlang/x").collect::<Vec<_>>(),
+        vec!["a/uses-x", "c/yanked"]
+    );
+    assert_eq!(
+        graph.deps("a/uses-x").collect::<Vec<_>>(),
+        vec!["moonbitlang/x"]
+    );
+    let latest = db.get("a/uses-x").unwrap().last().unwrap();
+    assert_eq!(latest.dep_version("moonbitlang/x"), Some("0.4.6"));
+    assert_eq!(
+        latest.repository.as_deref(),
+        Some("https://github.com/a/uses-x")
+    );
+    assert_eq!(
+        db.get("b/old").unwrap()[0].dep_version("moonbitlang/x"),
+        Some("0.4.0")
+    );
+}
+
+#[test]
+fn latest_skips_yanked_versions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = index_of_pkg(tmp.path(), "a", "lib");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(
+        path,
+        [
+            r#"{"name":"a/lib","version":"0.1.0","deprecated":"use 0.2.0"}"#,
+            r#"{"name":"a/lib","version":"0.2.0"}"#,
+            r#"{"name":"a/lib","version":"0.3.0","yanked":true}"#,
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    let db = get_mooncakes_at(tmp.path()).unwrap();
+    assert_eq!(db.get_latest_version("a/lib").unwrap(), "0.2.0");
+    assert!(db.get_version("a/lib", "0.3.0").unwrap().yanked);
+    assert!(db.get_version("a/lib", "0.1.0").unwrap().is_deprecated());
+}
+
+#[test]
+fn custom_registry_from_config() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = tmp.path().join("registries.json");
+    std::fs::write(
+        &config,
+        r#"{"acme": {"index": "/srv/acme/index", "download_url": "https://mirror.acme.dev/{name}/{version}.zip"}}"#,
+    )
+    .unwrap();
+    let registries = Registries::load(Some(&config)).unwrap();
+    let acme = registries.get(Some("acme")).unwrap();
+    assert_eq!(acme.name(), "acme");
+    assert_eq!(acme.index(), PathBuf::from("/srv/acme/index"));
+    assert_eq!(
+        acme.download_url("a/lib", "0.1.0+build"),
+        "https://mirror.acme.dev/a/lib/0.1.0%2Bbuild.zip"
+    );
+    assert_eq!(registries.get(None).unwrap().name(), PUBLIC_REGISTRY);
+    assert!(registries.get(Some("unknown")).is_none());
+    assert_eq!(registries.all().count(), 2);
 }
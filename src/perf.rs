@@ -0,0 +1,309 @@
+use std::{collections::HashMap, fmt::Write};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::dashboard::{MoonBuildDashboard, MoonCommand, ToolChainLabel};
+
+/// Fewer samples than this are too noisy to establish a baseline.
+const MIN_SAMPLES: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct PerfOptions {
+    /// Number of previous runs the baseline is computed from.
+    pub window: usize,
+    /// Relative change from the baseline median to flag, e.g. `0.2` for 20%.
+    pub threshold: f64,
+    /// Absolute change in milliseconds below which a change is noise.
+    pub min_delta_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum Direction {
+    Slowdown,
+    Speedup,
+}
+
+/// What changed between the most recent baseline sample and the current run.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum Attribution {
+    ToolChain,
+    Source,
+    ToolChainAndSource,
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerfChange {
+    pub source: String,
+    pub version: String,
+    pub toolchain: ToolChainLabel,
+    pub cell: String,
+    pub elapsed: u64,
+    pub baseline: u64,
+    pub samples: usize,
+    /// `elapsed / baseline`
+    pub ratio: f64,
+    pub direction: Direction,
+    pub attribution: Attribution,
+    pub moonc_before: String,
+    pub moonc_after: String,
+    pub revision_before: Option<String>,
+    pub revision_after: Option<String>,
+}
+
+type CellKey = (String, String, ToolChainLabel, MoonCommand);
+
+struct Sample {
+    elapsed: u64,
+    moonc_version: String,
+    revision: Option<String>,
+}
+
+fn successful_samples(run: &MoonBuildDashboard) -> HashMap<CellKey, Sample> {
+    let mut samples = HashMap::new();
+    for label in ToolChainLabel::all() {
+        let moonc_version = &run.toolchain_version(label).moonc_version;
+        for cell in run.cells(label) {
+            if !cell.result.status.is_success() || cell.result.carried_over.is_some() {
+                continue;
+            }
+            samples.insert(
+                (
+                    cell.source.full_name(),
+                    cell.version.to_string(),
+                    label,
+                    cell.cmd,
+                ),
+                Sample {
+                    elapsed: cell.result.elapsed,
+                    moonc_version: moonc_version.clone(),
+                    revision: cell.revision.map(|r| r.to_string()),
+                },
+            );
+        }
+    }
+    samples
+}
+
+fn median(xs: &mut [u64]) -> u64 {
+    xs.sort_unstable();
+    let mid = xs.len() / 2;
+    if xs.len() % 2 == 0 {
+        (xs[mid - 1] + xs[mid]) / 2
+    } else {
+        xs[mid]
+    }
+}
+
+/// Compares every successful cell of the last run against the median of the
+/// same cell over the previous `window` runs, ranked by magnitude per toolchain.
+pub fn detect_changes(runs: &[MoonBuildDashboard], options: &PerfOptions) -> Vec<PerfChange> {
+    let Some((latest, previous)) = runs.split_last() else {
+        return vec![];
+    };
+    // most recent first
+    let history: Vec<HashMap<CellKey, Sample>> = previous
+        .iter()
+        .rev()
+        .take(options.window)
+        .map(successful_samples)
+        .collect();
+
+    let mut changes = vec![];
+    for (key, current) in successful_samples(latest) {
+        let samples: Vec<&Sample> = history.iter().filter_map(|run| run.get(&key)).collect();
+        if samples.len() < MIN_SAMPLES {
+            continue;
+        }
+        let mut elapsed: Vec<u64> = samples.iter().map(|s| s.elapsed).collect();
+        let baseline = median(&mut elapsed).max(1);
+        let delta = current.elapsed.abs_diff(baseline);
+        let ratio = current.elapsed as f64 / baseline as f64;
+        if delta < options.min_delta_ms || (ratio - 1.0).abs() < options.threshold {
+            continue;
+        }
+
+        let last = samples[0];
+        let toolchain_changed = last.moonc_version != current.moonc_version;
+        let source_changed = last.revision != current.revision;
+        let attribution = match (toolchain_changed, source_changed) {
+            (true, true) => Attribution::ToolChainAndSource,
+            (true, false) => Attribution::ToolChain,
+            (false, true) => Attribution::Source,
+            (false, false) => Attribution::Unknown,
+        };
+        let (source, version, toolchain, cmd) = key;
+        changes.push(PerfChange {
+            source,
+            version,
+            toolchain,
+            cell: cmd.to_string(),
+            elapsed: current.elapsed,
+            baseline,
+            samples: samples.len(),
+            ratio,
+            direction: if current.elapsed > baseline {
+                Direction::Slowdown
+            } else {
+                Direction::Speedup
+            },
+            attribution,
+            moonc_before: last.moonc_version.clone(),
+            moonc_after: current.moonc_version,
+            revision_before: last.revision.clone(),
+            revision_after: current.revision,
+        });
+    }
+    changes.sort_by(|a, b| {
+        a.toolchain
+            .cmp(&b.toolchain)
+            .then(b.ratio.ln().abs().total_cmp(&a.ratio.ln().abs()))
+    });
+    changes
+}
+
+pub fn render_changes(changes: &[PerfChange]) -> String {
+    let mut out = String::new();
+    if changes.is_empty() {
+        let _ = writeln!(out, "no significant build time changes");
+        return out;
+    }
+    for label in ToolChainLabel::all() {
+        let changes: Vec<&PerfChange> = changes.iter().filter(|c| c.toolchain == label).collect();
+        if changes.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "{}", format!("{} toolchain", label).bold());
+        for c in changes {
+            let percent = format!("{:+.0}%", (c.ratio - 1.0) * 100.0);
+            let percent = match c.direction {
+                Direction::Slowdown => percent.red().bold(),
+                Direction::Speedup => percent.green().bold(),
+            };
+            let cause = match c.attribution {
+                Attribution::ToolChain => {
+                    format!("toolchain {} -> {}", c.moonc_before, c.moonc_after)
+                }
+                Attribution::Source => format!(
+                    "source {} -> {}",
+                    c.revision_before.as_deref().unwrap_or("?"),
+                    c.revision_after.as_deref().unwrap_or("?")
+                ),
+                Attribution::ToolChainAndSource => "toolchain and source changed".to_string(),
+                Attribution::Unknown => "no toolchain or source change".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "  {:>6} {} {} {}: {}ms vs median {}ms over {} runs ({})",
+                percent, c.source, c.version, c.cell, c.elapsed, c.baseline, c.samples, cause
+            );
+        }
+    }
+    out
+}
+
+/// A run of `a/a` and `b/b` built on both toolchains, every cell taking `elapsed`.
+#[cfg(test)]
+fn timed_run(run_id: &str, elapsed: u64) -> MoonBuildDashboard {
+    use crate::dashboard::{test_build, test_dashboard, Status};
+
+    let mut run = test_dashboard(run_id, &["a/a", "b/b"]);
+    let build = |source| {
+        let mut state = test_build(source, "0.1.0", Status::Success);
+        let cbt = state.cbts[0].as_mut().unwrap();
+        for backends in [&mut cbt.check, &mut cbt.build, &mut cbt.test] {
+            for result in [&mut backends.wasm, &mut backends.wasm_gc, &mut backends.js] {
+                result.elapsed = elapsed;
+            }
+        }
+        state
+    };
+    run.stable_release_data = vec![build(0), build(1)];
+    run.bleeding_release_data = vec![build(0), build(1)];
+    run
+}
+
+#[cfg(test)]
+const TEST_OPTIONS: PerfOptions = PerfOptions {
+    window: 10,
+    threshold: 0.2,
+    min_delta_ms: 100,
+};
+
+#[test]
+fn slowdown_is_attributed_to_toolchain() {
+    let mut runs: Vec<MoonBuildDashboard> = [1000, 1100, 900, 1000]
+        .iter()
+        .enumerate()
+        .map(|(i, elapsed)| timed_run(&i.to_string(), *elapsed))
+        .collect();
+    let mut latest = timed_run("4", 1050);
+    latest.bleeding_release_data[0].cbts[0]
+        .as_mut()
+        .unwrap()
+        .build
+        .js
+        .elapsed = 2000;
+    latest.bleeding_toolchain_version.moonc_version = "v0.3.0".to_string();
+    runs.push(latest);
+
+    let changes = detect_changes(&runs, &TEST_OPTIONS);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(
+        (changes[0].source.as_str(), changes[0].cell.as_str()),
+        ("a/a", "build:js")
+    );
+    assert_eq!(changes[0].baseline, 1000);
+    assert_eq!(changes[0].direction, Direction::Slowdown);
+    assert_eq!(changes[0].attribution, Attribution::ToolChain);
+}
+
+#[test]
+fn carried_over_failing_and_unsampled_cells_are_skipped() {
+    use crate::dashboard::Status;
+
+    let mut runs: Vec<MoonBuildDashboard> =
+        (0..3).map(|i| timed_run(&i.to_string(), 1000)).collect();
+    // b/b was not built in one run, leaving two samples for its cells
+    runs[1].stable_release_data.pop();
+    let mut latest = timed_run("3", 3000);
+    // a/a on bleeding was carried over, and failed on stable
+    for state in &mut latest.bleeding_release_data {
+        let cbt = state.cbts[0].as_mut().unwrap();
+        for backends in [&mut cbt.check, &mut cbt.build, &mut cbt.test] {
+            for result in [&mut backends.wasm, &mut backends.wasm_gc, &mut backends.js] {
+                result.carried_over = Some("2".to_string());
+            }
+        }
+    }
+    let cbt = latest.stable_release_data[0].cbts[0].as_mut().unwrap();
+    for backends in [&mut cbt.check, &mut cbt.build, &mut cbt.test] {
+        for result in [&mut backends.wasm, &mut backends.wasm_gc, &mut backends.js] {
+            result.status = Status::Failure;
+        }
+    }
+    runs.push(latest);
+    assert!(detect_changes(&runs, &TEST_OPTIONS).is_empty());
+
+    // a speedup is reported once enough samples are there
+    let mut runs: Vec<MoonBuildDashboard> =
+        (0..3).map(|i| timed_run(&i.to_string(), 1000)).collect();
+    let mut latest = timed_run("3", 1000);
+    let result = &mut latest.stable_release_data[1].cbts[0]
+        .as_mut()
+        .unwrap()
+        .test
+        .wasm;
+    result.elapsed = 500;
+    latest.stable_release_data[1].revisions = vec![Some("0.1.1".to_string())];
+    runs.push(latest);
+    let changes = detect_changes(&runs, &TEST_OPTIONS);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(
+        (changes[0].source.as_str(), changes[0].cell.as_str()),
+        ("b/b", "test:wasm")
+    );
+    assert_eq!(changes[0].direction, Direction::Speedup);
+    assert_eq!(changes[0].attribution, Attribution::Source);
+}
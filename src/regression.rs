@@ -0,0 +1,302 @@
+use std::fmt::Write;
+
+use colored::Colorize;
+
+use crate::{
+    dashboard::{
+        Backend, BackendComparison, BuildState, CBTComparison, Comparison, ComparisonState,
+        MoonBuildDashboard, MoonCommand, MooncakeSource, ToolChainLabel, CBT,
+    },
+    deps::{blocked_failures, blocked_keys},
+    flaky::is_flaky,
+};
+
+fn compare_cbt(stable: &CBT, bleeding: &CBT) -> CBTComparison {
+    let backends = |cmd: fn(Backend) -> MoonCommand| {
+        let c = |backend| {
+            let cmd = cmd(backend);
+            Comparison::classify(stable.get(cmd).status, bleeding.get(cmd).status)
+        };
+        BackendComparison {
+            wasm: c(Backend::Wasm),
+            wasm_gc: c(Backend::WasmGC),
+            js: c(Backend::Js),
+        }
+    };
+    CBTComparison {
+        check: backends(MoonCommand::Check),
+        build: backends(MoonCommand::Build),
+        test: backends(MoonCommand::Test),
+    }
+}
+
+/// Classifies every cell of a run by comparing its bleeding result with the
+/// stable one of the same source and version.
+pub fn compare_toolchains(stable: &[BuildState], bleeding: &[BuildState]) -> Vec<ComparisonState> {
+    stable
+        .iter()
+        .map(|stable_state| {
+            let bleeding_state = bleeding.iter().find(|b| b.source == stable_state.source);
+            let cbts = stable_state
+                .cbts
+                .iter()
+                .enumerate()
+                .map(|(i, stable_cbt)| {
+                    let bleeding_cbt = bleeding_state
+                        .and_then(|b| b.cbts.get(i))
+                        .and_then(|c| c.as_ref());
+                    match (stable_cbt, bleeding_cbt) {
+                        (Some(s), Some(b)) => Some(compare_cbt(s, b)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            ComparisonState {
+                source: stable_state.source,
+                cbts,
+            }
+        })
+        .collect()
+}
+
+/// A source version that passes some cells on stable but fails them on bleeding.
+#[derive(Debug)]
+pub struct Regression<'a> {
+    pub source: &'a MooncakeSource,
+    pub version: &'a str,
+    pub cmds: Vec<MoonCommand>,
+    pub priority: u32,
+    /// The source is often flaky, so the regression may be a false alarm and
+    /// is listed apart from the others.
+    pub flaky: bool,
+}
+
+/// A `check` regression blocks everything downstream, so it weighs more
+/// than a `build` one, which weighs more than a `test` one.
+fn weight(cmd: MoonCommand) -> u32 {
+    match cmd {
+        MoonCommand::Check(_) => 3,
+        MoonCommand::Build(_) => 2,
+        MoonCommand::Test(_) => 1,
+    }
+}
+
+/// Lists regressions of a run, most severe first. Regressions of flaky sources come
+/// last, marked as such, and are not counted as regressions. Uses the stored
+/// `toolchain_comparison` and falls back to computing it for older runs. Cells blocked
+/// by a failing tracked dependency are left out.
+pub fn prioritized_regressions(run: &MoonBuildDashboard) -> Vec<Regression<'_>> {
+    let computed;
+    let comparison = if run.toolchain_comparison.is_empty() {
+        computed = compare_toolchains(&run.stable_release_data, &run.bleeding_release_data);
+        &computed
+    } else {
+        &run.toolchain_comparison
+    };
+
+    let blocked = blocked_failures(run);
+    let blocked = blocked_keys(&blocked);
+    let mut regressions = vec![];
+    for state in comparison {
+        let Some(source) = run.sources.get(state.source) else {
+            continue;
+        };
+        let name = source.full_name();
+        for (i, cbt) in state.cbts.iter().enumerate() {
+            let Some(cbt) = cbt else {
+                continue;
+            };
+            let version = source.versions().get(i).map_or("", |v| v.as_str());
+            let cmds: Vec<MoonCommand> = MoonCommand::all()
+                .into_iter()
+                .filter(|cmd| cbt.get(*cmd) == Comparison::Regression)
+                .filter(|cmd| {
+                    !blocked.contains(&(ToolChainLabel::Bleeding, name.as_str(), version, *cmd))
+                })
+                .collect();
+            if cmds.is_empty() {
+                continue;
+            }
+            regressions.push(Regression {
+                source,
+                version,
+                priority: cmds.iter().map(|cmd| weight(*cmd)).sum(),
+                cmds,
+                flaky: is_flaky(run, source),
+            });
+        }
+    }
+    regressions.sort_by_key(|r| (r.flaky, std::cmp::Reverse(r.priority)));
+    regressions
+}
+
+pub fn render_regressions(run: &MoonBuildDashboard, regressions: &[Regression]) -> String {
+    let mut out = String::new();
+    let (flaky, regressions): (Vec<&Regression>, Vec<&Regression>) =
+        regressions.iter().partition(|r| r.flaky);
+    if regressions.is_empty() {
+        let _ = writeln!(
+            out,
+            "{}",
+            "no regressions from stable to bleeding".green().bold()
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "{}",
+            format!(
+                "{} regressions from stable ({}) to bleeding ({}):",
+                regressions.len(),
+                run.toolchain_version(ToolChainLabel::Stable).moonc_version,
+                run.toolchain_version(ToolChainLabel::Bleeding)
+                    .moonc_version
+            )
+            .red()
+            .bold()
+        );
+        render_list(&mut out, &regressions);
+    }
+    if !flaky.is_empty() {
+        let _ = writeln!(
+            out,
+            "{}",
+            format!(
+                "{} failing on bleeding in flaky sources, not counted:",
+                flaky.len()
+            )
+            .yellow()
+            .bold()
+        );
+        render_list(&mut out, &flaky);
+    }
+    out
+}
+
+fn render_list(out: &mut String, regressions: &[&Regression]) {
+    for r in regressions {
+        let cmds: Vec<String> = r.cmds.iter().map(|c| c.to_string()).collect();
+        let _ = writeln!(
+            out,
+            "  [{:>2}] {} {}: {}",
+            r.priority,
+            r.source.name(),
+            r.version,
+            cmds.join(" ")
+        );
+    }
+}
+
+#[test]
+fn regressions_are_prioritized() {
+    use crate::dashboard::{test_build, test_dashboard, Status};
+
+    // on bleeding a/a fails test:js, b/b every check, c/c fails everything on
+    // both toolchains and d/d was not built on bleeding
+    let mut run = test_dashboard("1", &["a/a", "b/b", "c/c", "d/d"]);
+    run.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Success),
+        test_build(1, "0.1.0", Status::Success),
+        test_build(2, "0.1.0", Status::Failure),
+        test_build(3, "0.1.0", Status::Success),
+    ];
+    let mut a = test_build(0, "0.1.0", Status::Success);
+    a.cbts[0].as_mut().unwrap().test.js.status = Status::Failure;
+    let mut b = test_build(1, "0.1.0", Status::Success);
+    let check = &mut b.cbts[0].as_mut().unwrap().check;
+    for result in [&mut check.wasm, &mut check.wasm_gc, &mut check.js] {
+        result.status = Status::Failure;
+    }
+    // bleeding data is matched by source, not by position
+    run.bleeding_release_data = vec![test_build(2, "0.1.0", Status::Failure), b, a];
+
+    let regressions = prioritized_regressions(&run);
+    let names: Vec<&str> = regressions.iter().map(|r| r.source.name()).collect();
+    assert_eq!(names, vec!["b/b", "a/a"]);
+    assert_eq!(regressions[0].priority, 9);
+    assert_eq!(regressions[1].cmds, vec![MoonCommand::Test(Backend::Js)]);
+}
+
+#[test]
+fn regressions_are_found_per_version() {
+    use crate::dashboard::{test_build, test_dashboard, Status};
+
+    // only the second tracked version of a/a regresses
+    let mut run = test_dashboard("1", &["a/a"]);
+    if let MooncakeSource::MooncakesIO { version, .. } = &mut run.sources[0] {
+        version.push("0.2.0".to_string());
+    }
+    let mut stable = test_build(0, "0.1.0", Status::Success);
+    stable.cbts.push(stable.cbts[0].clone());
+    let mut bleeding = stable.clone();
+    bleeding.cbts[1].as_mut().unwrap().build.wasm.status = Status::Failure;
+    // the stored comparison is used when present
+    run.toolchain_comparison = compare_toolchains(&[stable], &[bleeding]);
+
+    let regressions = prioritized_regressions(&run);
+    assert_eq!(regressions.len(), 1);
+    assert_eq!(regressions[0].version, "0.2.0");
+    assert_eq!(regressions[0].cmds, vec![MoonCommand::Build(Backend::Wasm)]);
+}
+
+#[test]
+fn flaky_sources_are_not_counted_as_regressions() {
+    use crate::{
+        dashboard::{test_build, test_dashboard, SourceFlakiness, Status},
+        diff::{diff_runs, CellChange},
+        report::render_markdown,
+    };
+
+    // both sources regress on bleeding build:js, and b/b is often flaky
+    let mut run = test_dashboard("1", &["a/a", "b/b"]);
+    for source in 0..2 {
+        run.stable_release_data
+            .push(test_build(source, "0.1.0", Status::Success));
+        let mut state = test_build(source, "0.1.0", Status::Success);
+        state.cbts[0].as_mut().unwrap().build.js.status = Status::Failure;
+        run.bleeding_release_data.push(state);
+    }
+    // a/a was flaky once, below the threshold
+    run.flakiness = vec![
+        SourceFlakiness {
+            source: "a/a".to_string(),
+            runs: 10,
+            flaky_runs: 1,
+            rate: 0.1,
+        },
+        SourceFlakiness {
+            source: "b/b".to_string(),
+            runs: 10,
+            flaky_runs: 5,
+            rate: 0.5,
+        },
+    ];
+    let regressions = prioritized_regressions(&run);
+    assert_eq!(regressions.len(), 2);
+    assert!(!regressions[0].flaky && regressions[1].flaky);
+
+    let text = render_regressions(&run, &regressions);
+    let (counted, flaky) = text.split_once("flaky sources, not counted").unwrap();
+    assert!(counted.contains("1 regressions") && counted.contains("a/a"));
+    assert!(!counted.contains("b/b") && flaky.contains("b/b"));
+
+    let md = render_markdown(&run, 3);
+    let (counted, flaky) = md
+        .split_once("### Flaky sources failing on bleeding")
+        .unwrap();
+    assert!(counted.contains("| a/a |") && !counted.contains("| b/b |"));
+    assert!(flaky.contains("| b/b | 0.1.0 | `build:js` |"));
+
+    // the same cells are flaky, not regressed, when diffing against a passing run
+    let mut passing = test_dashboard("0", &["a/a", "b/b"]);
+    passing.stable_release_data = run.stable_release_data.clone();
+    passing.bleeding_release_data = run.stable_release_data.clone();
+    let diff = diff_runs(&passing, &run);
+    let change = |source: &str| {
+        diff.cells
+            .iter()
+            .find(|c| c.source == source && c.toolchain == ToolChainLabel::Bleeding)
+            .map(|c| c.change)
+    };
+    assert_eq!(change("a/a"), Some(CellChange::Regressed));
+    assert_eq!(change("b/b"), Some(CellChange::Flaky));
+}
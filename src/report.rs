@@ -0,0 +1,416 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    cluster::cluster_failures,
+    dashboard::{Cell, MoonBuildDashboard, MoonCommand, ResolutionMode, Status, ToolChainLabel},
+    deps::blocked_failures,
+    regression::{prioritized_regressions, Regression},
+    upstream::{upstream_cells, UpstreamCell},
+};
+
+/// The `n` slowest passing cells built in this run, carried-over cells keep
+/// the timings of the run that built them.
+pub fn slowest_cells(run: &MoonBuildDashboard, n: usize) -> Vec<Cell<'_>> {
+    let mut cells: Vec<Cell> = ToolChainLabel::all()
+        .into_iter()
+        .flat_map(|label| run.cells(label))
+        .filter(|cell| cell.result.status.is_success() && cell.result.carried_over.is_none())
+        .collect();
+    cells.sort_by_key(|cell| std::cmp::Reverse(cell.result.elapsed));
+    cells.truncate(n);
+    cells
+}
+
+pub(crate) fn pass_rate(passed: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", passed as f64 * 100.0 / total as f64)
+    }
+}
+
+/// Renders a run as Markdown, suitable for release notes, PR comments and
+/// `$GITHUB_STEP_SUMMARY`.
+pub fn render_markdown(run: &MoonBuildDashboard, slowest: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "## Moon Build Dashboard — run {} ({})\n",
+        run.run_number, run.run_id
+    );
+    let _ = writeln!(out, "Started at {}\n", run.start_time);
+
+    let _ = writeln!(out, "### Summary\n");
+    let _ = writeln!(
+        out,
+        "| Toolchain | moon | moonc | Passed | Failed | Pass rate |"
+    );
+    let _ = writeln!(out, "|---|---|---|---:|---:|---:|");
+    for label in ToolChainLabel::all() {
+        let version = run.toolchain_version(label);
+        let cells = run.cells(label);
+        let passed = cells
+            .iter()
+            .filter(|c| c.result.status.is_success())
+            .count();
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} |",
+            label,
+            version.moon_version,
+            version.moonc_version,
+            passed,
+            cells.len() - passed,
+            pass_rate(passed, cells.len())
+        );
+    }
+    let _ = writeln!(out);
+
+    let resolutions: BTreeSet<ResolutionMode> = run
+        .stable_release_data
+        .iter()
+        .chain(&run.bleeding_release_data)
+        .flat_map(|b| b.resolutions.iter().copied())
+        .filter(|r| *r != ResolutionMode::AsPublished)
+        .collect();
+    for r in resolutions {
+        let _ = writeln!(out, "Dependencies resolved as `{}`.\n", r);
+    }
+    let cells: Vec<Cell> = ToolChainLabel::all()
+        .into_iter()
+        .flat_map(|label| run.cells(label))
+        .collect();
+    let carried = cells
+        .iter()
+        .filter(|c| c.result.carried_over.is_some())
+        .count();
+    if carried > 0 {
+        let _ = writeln!(
+            out,
+            "{} of {} cells carried over from earlier runs.\n",
+            carried,
+            cells.len()
+        );
+    }
+
+    let cmds = MoonCommand::all();
+    let _ = write!(out, "| Toolchain |");
+    for cmd in &cmds {
+        let _ = write!(out, " {} |", cmd);
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "|---|{}", "---:|".repeat(cmds.len()));
+    for label in ToolChainLabel::all() {
+        let cells = run.cells(label);
+        let _ = write!(out, "| {} |", label);
+        for cmd in &cmds {
+            let total = cells.iter().filter(|c| c.cmd == *cmd).count();
+            let passed = cells
+                .iter()
+                .filter(|c| c.cmd == *cmd && c.result.status.is_success())
+                .count();
+            let _ = write!(out, " {}/{} |", passed, total);
+        }
+        let _ = writeln!(out);
+    }
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "### Failing on bleeding, passing on stable\n");
+    let regressions = prioritized_regressions(run);
+    let (flaky, regressions): (Vec<_>, Vec<_>) = regressions.iter().partition(|r| r.flaky);
+    let table = |out: &mut String, regressions: &[&Regression]| {
+        let _ = writeln!(out, "| Source | Version | Cells |");
+        let _ = writeln!(out, "|---|---|---|");
+        for r in regressions {
+            let cells: Vec<String> = r.cmds.iter().map(|c| format!("`{}`", c)).collect();
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                r.source.name(),
+                r.version,
+                cells.join(" ")
+            );
+        }
+        let _ = writeln!(out);
+    };
+    if regressions.is_empty() {
+        let _ = writeln!(out, "_None_\n");
+    } else {
+        table(&mut out, &regressions);
+    }
+    if !flaky.is_empty() {
+        let _ = writeln!(out, "### Flaky sources failing on bleeding\n");
+        let _ = writeln!(
+            out,
+            "These sources are often flaky and are not counted as regressions.\n"
+        );
+        table(&mut out, &flaky);
+    }
+
+    let yanked: Vec<String> = run
+        .sources
+        .iter()
+        .flat_map(|source| {
+            source
+                .versions()
+                .iter()
+                .filter(|v| source.is_yanked(v))
+                .map(move |v| format!("{} {}", source.name(), v))
+        })
+        .collect();
+    if !yanked.is_empty() {
+        let _ = writeln!(out, "### Yanked versions built\n");
+        for v in &yanked {
+            let _ = writeln!(out, "- {}", v);
+        }
+        let _ = writeln!(out);
+    }
+
+    let mut broken: Vec<String> = vec![];
+    for label in ToolChainLabel::all() {
+        for state in run.release_data(label) {
+            let Some(source) = run.sources.get(state.source) else {
+                continue;
+            };
+            for (version, violation) in &state.integrity {
+                let line = format!("{} {}: {}", source.name(), version, violation);
+                // both toolchains download the same package
+                if !broken.contains(&line) {
+                    broken.push(line);
+                }
+            }
+        }
+    }
+    if !broken.is_empty() {
+        let _ = writeln!(out, "### Broken packages\n");
+        for b in &broken {
+            let _ = writeln!(out, "- {}", b);
+        }
+        let _ = writeln!(out);
+    }
+
+    let violations: Vec<Cell> = ToolChainLabel::all()
+        .into_iter()
+        .flat_map(|label| run.cells(label))
+        .filter(|c| c.result.status == Status::SandboxViolation)
+        .collect();
+    if !violations.is_empty() {
+        let _ = writeln!(out, "### Sandbox violations\n");
+        let _ = writeln!(out, "| Toolchain | Source | Version | Cell | Denied |");
+        let _ = writeln!(out, "|---|---|---|---|---|");
+        for c in &violations {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | `{}` | {} |",
+                c.label,
+                c.source.name(),
+                c.version,
+                c.cmd,
+                c.result.diagnostics.first().map_or("", |d| d.as_str())
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    let exceeded: Vec<Cell> = ToolChainLabel::all()
+        .into_iter()
+        .flat_map(|label| run.cells(label))
+        .filter(|c| matches!(c.result.status, Status::ResourceExceeded { .. }))
+        .collect();
+    if !exceeded.is_empty() {
+        let _ = writeln!(out, "### Resource limits exceeded\n");
+        let _ = writeln!(out, "| Toolchain | Source | Version | Cell | Limit |");
+        let _ = writeln!(out, "|---|---|---|---|---|");
+        for c in &exceeded {
+            if let Status::ResourceExceeded { kind } = c.result.status {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | `{}` | {} |",
+                    c.label,
+                    c.source.name(),
+                    c.version,
+                    c.cmd,
+                    kind
+                );
+            }
+        }
+        let _ = writeln!(out);
+    }
+
+    let _ = writeln!(out, "### Failure clusters\n");
+    let clusters = cluster_failures(run);
+    if clusters.is_empty() {
+        let _ = writeln!(out, "_None_\n");
+    } else {
+        let _ = writeln!(out, "| Toolchain | Signature | Cells | Sources | Example |");
+        let _ = writeln!(out, "|---|---|---:|---|---|");
+        for c in &clusters {
+            let mut sources = c
+                .sources
+                .iter()
+                .take(10)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if c.sources.len() > 10 {
+                let _ = write!(sources, " and {} more", c.sources.len() - 10);
+            }
+            let example = c
+                .example
+                .as_ref()
+                .map(|(source, d)| format!("{}: `` {} ``", source, d))
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "| {} | `` {} `` | {} | {} | {} |",
+                c.toolchain,
+                c.signature.replace('|', "\\|"),
+                c.cells,
+                sources,
+                example.replace('|', "\\|")
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    let blocked = blocked_failures(run);
+    if !blocked.is_empty() {
+        let _ = writeln!(out, "### Blocked by failing dependencies\n");
+        let _ = writeln!(
+            out,
+            "| Toolchain | Source | Version | Cells | Blocked by | Root |"
+        );
+        let _ = writeln!(out, "|---|---|---|---|---|---|");
+        let mut rows: BTreeMap<_, Vec<String>> = BTreeMap::new();
+        for b in &blocked {
+            rows.entry((b.toolchain, &b.source, &b.version, &b.upstream, &b.root))
+                .or_default()
+                .push(format!("`{}`", b.cmd));
+        }
+        for ((toolchain, source, version, upstream, root), cells) in rows {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} |",
+                toolchain,
+                source,
+                version,
+                cells.join(" "),
+                upstream,
+                root
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    let upstream = upstream_cells(run);
+    if !upstream.is_empty() {
+        let _ = writeln!(out, "### Published vs upstream HEAD\n");
+        let _ = writeln!(
+            out,
+            "| Toolchain | Source | Published | Upstream HEAD | Fixed upstream | Broken upstream |"
+        );
+        let _ = writeln!(out, "|---|---|---|---|---|---|");
+        let mut rows: BTreeMap<(ToolChainLabel, &str), Vec<&UpstreamCell>> = BTreeMap::new();
+        for c in &upstream {
+            rows.entry((c.toolchain, c.source.as_str()))
+                .or_default()
+                .push(c);
+        }
+        for ((label, source), cells) in rows {
+            let passed = |status: fn(&UpstreamCell) -> Status| {
+                cells.iter().filter(|c| status(c).is_success()).count()
+            };
+            let list = |pick: fn(&UpstreamCell) -> bool| {
+                let cmds: Vec<String> = cells
+                    .iter()
+                    .filter(|c| pick(c))
+                    .map(|c| format!("`{}`", c.cmd))
+                    .collect();
+                cmds.join(" ")
+            };
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} {}/{} | {} {}/{} | {} | {} |",
+                label,
+                source,
+                cells[0].version,
+                passed(|c| c.published),
+                cells.len(),
+                cells[0].revision,
+                passed(|c| c.upstream),
+                cells.len(),
+                list(|c| c.fixed_upstream()),
+                list(|c| c.published.is_success() && !c.upstream.is_success()),
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    let _ = writeln!(out, "### Slowest cells\n");
+    let _ = writeln!(
+        out,
+        "| Source | Version | Toolchain | Cell | Elapsed (ms) |"
+    );
+    let _ = writeln!(out, "|---|---|---|---|---:|");
+    for cell in slowest_cells(run, slowest) {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | `{}` | {} |",
+            cell.source.name(),
+            cell.version,
+            cell.label,
+            cell.cmd,
+            cell.result.elapsed
+        );
+    }
+    out
+}
+
+#[test]
+fn markdown_lists_bleeding_regressions() {
+    use crate::dashboard::{test_build, test_dashboard};
+
+    // a/a regresses on bleeding build:js, b/b fails everywhere and c/c was
+    // only built on stable, its results carried over from run 1
+    let mut run = test_dashboard("2", &["a/a", "b/b", "c/c"]);
+    let mut carried = test_build(2, "0.1.0", Status::Success);
+    carried.cbts[0].as_mut().unwrap().test.wasm.carried_over = Some("1".to_string());
+    run.stable_release_data = vec![
+        test_build(0, "0.1.0", Status::Success),
+        test_build(1, "0.1.0", Status::Failure),
+        carried,
+    ];
+    let mut regressed = test_build(0, "0.1.0", Status::Success);
+    regressed.cbts[0].as_mut().unwrap().build.js.status = Status::Failure;
+    run.bleeding_release_data = vec![regressed, test_build(1, "0.1.0", Status::Failure)];
+
+    let md = render_markdown(&run, 3);
+    assert!(md.contains("| stable | moon 0.1.0 | v0.1.0 | 18 | 9 | 66.7% |"));
+    assert!(md.contains("| bleeding | moon 0.2.0 | v0.2.0 | 8 | 10 | 44.4% |"));
+    assert!(md.contains("1 of 45 cells carried over from earlier runs."));
+    assert!(md.contains("| a/a | 0.1.0 | `build:js` |"));
+    assert!(!md.contains("| b/b | 0.1.0 |"));
+}
+
+#[test]
+fn slowest_cells_skip_carried_over_results() {
+    use crate::dashboard::{test_build, test_dashboard, Backend};
+
+    let mut run = test_dashboard("2", &["a/a", "b/b"]);
+    let mut built = test_build(0, "0.1.0", Status::Success);
+    built.cbts[0].as_mut().unwrap().test.js.elapsed = 500;
+    // b/b's build:js was reused from run 1, where it took far longer
+    let mut carried = test_build(1, "0.1.0", Status::Success);
+    let build_js = &mut carried.cbts[0].as_mut().unwrap().build.js;
+    build_js.elapsed = 9000;
+    build_js.carried_over = Some("1".to_string());
+    run.stable_release_data = vec![built, carried];
+
+    let slowest = slowest_cells(&run, 1);
+    assert_eq!(slowest.len(), 1);
+    assert_eq!(slowest[0].source.name(), "a/a");
+    assert_eq!(slowest[0].cmd, MoonCommand::Test(Backend::Js));
+}
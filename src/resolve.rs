@@ -0,0 +1,179 @@
+use std::path::Path;
+
+use crate::{
+    dashboard::ResolutionMode,
+    mooncakesio::{self, Registry},
+};
+
+#[derive(Debug, thiserror::Error)]
+#[error("resolve error")]
+pub struct ResolveError {
+    #[source]
+    kind: ResolveErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResolveErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("serde")]
+    Serde(#[from] serde_json::Error),
+    #[error("failed on mooncakesio")]
+    MooncakesIO(#[from] mooncakesio::MooncakesIOError),
+}
+
+/// A dependency whose version was rewritten.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DepChange {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// The lowest version a requirement such as `0.4.6`, `^0.4.6` or
+/// `>=0.4.6, <0.5.0` allows, `None` when it has no inclusive lower bound.
+pub(crate) fn minimum(requirement: &str) -> Option<&str> {
+    requirement.split(',').find_map(|comparator| {
+        let comparator = comparator.trim();
+        if comparator.starts_with('<')
+            || comparator.starts_with('>') && !comparator.starts_with(">=")
+        {
+            return None;
+        }
+        let version = comparator
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .trim();
+        (!version.is_empty()).then_some(version)
+    })
+}
+
+/// Chooses the version of every dependency in `moon.mod.json` according to `mode`,
+/// using `latest` to look up the latest version of a registry package.
+fn resolve(
+    json: &mut serde_json::Value,
+    mode: ResolutionMode,
+    mut latest: impl FnMut(&str) -> Result<Option<String>, ResolveError>,
+) -> Result<Vec<DepChange>, ResolveError> {
+    let mut changes = vec![];
+    let Some(deps) = json.get_mut("deps").and_then(|d| d.as_object_mut()) else {
+        return Ok(changes);
+    };
+    for (name, requirement) in deps.iter_mut() {
+        // "0.4.6" or { "version": "0.4.6" }, local { "path": ... } dependencies are left alone
+        let slot = if requirement.is_string() {
+            requirement
+        } else {
+            match requirement.get_mut("version") {
+                Some(version) if version.is_string() => version,
+                _ => continue,
+            }
+        };
+        let from = slot.as_str().unwrap_or_default().to_string();
+        let to = match mode {
+            ResolutionMode::AsPublished => continue,
+            ResolutionMode::Latest => match latest(name)? {
+                Some(version) => version,
+                None => continue,
+            },
+            ResolutionMode::Minimal => match minimum(&from) {
+                Some(version) => version.to_string(),
+                None => continue,
+            },
+        };
+        if to != from {
+            *slot = serde_json::Value::String(to.clone());
+            changes.push(DepChange {
+                name: name.clone(),
+                from,
+                to,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Rewrites the dependencies in `workdir/moon.mod.json` according to `mode`,
+/// looking up latest versions in `registry`. Sources without a `moon.mod.json`
+/// are left untouched.
+pub fn apply_resolution(
+    workdir: &Path,
+    mode: ResolutionMode,
+    registry: &dyn Registry,
+) -> Result<Vec<DepChange>, ResolveError> {
+    let path = workdir.join("moon.mod.json");
+    if mode == ResolutionMode::AsPublished || !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| ResolveError {
+        kind: ResolveErrorKind::IOError(e),
+    })?;
+    let mut json: serde_json::Value = serde_json::from_str(&content).map_err(|e| ResolveError {
+        kind: ResolveErrorKind::Serde(e),
+    })?;
+    let changes = resolve(&mut json, mode, |name| {
+        let versions = registry.get_package(name).map_err(|e| ResolveError {
+            kind: ResolveErrorKind::MooncakesIO(e),
+        })?;
+        Ok(versions
+            .iter()
+            .rev()
+            .find(|v| !v.yanked)
+            .map(|v| v.version.clone()))
+    })?;
+    if !changes.is_empty() {
+        let content = serde_json::to_string_pretty(&json).map_err(|e| ResolveError {
+            kind: ResolveErrorKind::Serde(e),
+        })?;
+        std::fs::write(&path, content).map_err(|e| ResolveError {
+            kind: ResolveErrorKind::IOError(e),
+        })?;
+    }
+    Ok(changes)
+}
+
+#[test]
+fn resolution_rewrites_dependency_versions() {
+    let module = serde_json::json!({
+        "name": "a/app",
+        "deps": {
+            "moonbitlang/x": "^0.4.6",
+            "a/lib": { "version": "0.1.0" },
+            "a/range": ">=0.2.1, <0.3.0",
+            "a/upper": "<1.0.0",
+            "a/local": { "path": "../local" },
+        },
+    });
+    let latest = |name: &str| Ok(Some(format!("{}-latest", name.len())));
+
+    let mut json = module.clone();
+    assert!(resolve(&mut json, ResolutionMode::AsPublished, latest)
+        .unwrap()
+        .is_empty());
+    assert_eq!(json, module);
+
+    let mut json = module.clone();
+    let changes = resolve(&mut json, ResolutionMode::Latest, latest).unwrap();
+    assert_eq!(changes.len(), 4);
+    assert_eq!(json["deps"]["moonbitlang/x"], "13-latest");
+    assert_eq!(json["deps"]["a/lib"]["version"], "5-latest");
+    assert_eq!(json["deps"]["a/local"], module["deps"]["a/local"]);
+
+    let mut json = module.clone();
+    let changes = resolve(&mut json, ResolutionMode::Minimal, latest).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            DepChange {
+                name: "a/range".to_string(),
+                from: ">=0.2.1, <0.3.0".to_string(),
+                to: "0.2.1".to_string(),
+            },
+            DepChange {
+                name: "moonbitlang/x".to_string(),
+                from: "^0.4.6".to_string(),
+                to: "0.4.6".to_string(),
+            },
+        ]
+    );
+    assert_eq!(json["deps"]["a/upper"], "<1.0.0");
+}
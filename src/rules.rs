@@ -0,0 +1,202 @@
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mooncakesio::MooncakeInfo;
+
+/// Rules file read when present and no other is given.
+pub const DEFAULT_RULES_FILE: &str = "registry-rules.json";
+
+#[derive(Debug, thiserror::Error)]
+#[error("rules error")]
+pub struct RulesError {
+    #[source]
+    kind: RulesErrorKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RulesErrorKind {
+    #[error("io error")]
+    IOError(#[from] std::io::Error),
+    #[error("serde")]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Which registry packages are tested. Name patterns are globs where `*`
+/// matches any run of characters and `?` any single one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegistryRules {
+    /// A package is excluded when any of its versions has one of these keywords.
+    pub exclude_keywords: Vec<String>,
+    /// When not empty, the latest version needs one of these keywords.
+    pub include_keywords: Vec<String>,
+    /// When not empty, only packages of these owners are included.
+    pub allow_owners: Vec<String>,
+    pub deny_owners: Vec<String>,
+    /// When not empty, only packages matching one of these patterns are included.
+    pub include_names: Vec<String>,
+    pub exclude_names: Vec<String>,
+    /// Package name to the reason it is excluded.
+    pub deny: BTreeMap<String, String>,
+}
+
+impl Default for RegistryRules {
+    fn default() -> Self {
+        RegistryRules {
+            exclude_keywords: vec!["mooncakes-test".to_string()],
+            include_keywords: vec![],
+            allow_owners: vec![],
+            deny_owners: vec![],
+            include_names: vec![],
+            exclude_names: vec![],
+            deny: BTreeMap::new(),
+        }
+    }
+}
+
+/// The rule that excluded a package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Exclusion {
+    Denied(String),
+    DeniedOwner(String),
+    OwnerNotAllowed,
+    ExcludedKeyword(String),
+    MissingKeyword,
+    ExcludedName(String),
+    NameNotIncluded,
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exclusion::Denied(reason) => write!(f, "denied: {}", reason),
+            Exclusion::DeniedOwner(owner) => write!(f, "owner {} is denied", owner),
+            Exclusion::OwnerNotAllowed => write!(f, "owner is not allowed"),
+            Exclusion::ExcludedKeyword(keyword) => write!(f, "has keyword {}", keyword),
+            Exclusion::MissingKeyword => write!(f, "has none of the included keywords"),
+            Exclusion::ExcludedName(pattern) => write!(f, "name matches {}", pattern),
+            Exclusion::NameNotIncluded => write!(f, "name matches no included pattern"),
+        }
+    }
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    // matched[j]: whether the pattern so far matches the first j chars of the name
+    let mut matched = vec![false; n.len() + 1];
+    matched[0] = true;
+    for c in p {
+        let mut next = vec![false; n.len() + 1];
+        for j in 0..=n.len() {
+            next[j] = match c {
+                '*' => matched[j] || (j > 0 && next[j - 1]),
+                '?' => j > 0 && matched[j - 1],
+                c => j > 0 && matched[j - 1] && n[j - 1] == c,
+            };
+        }
+        matched = next;
+    }
+    matched[n.len()]
+}
+
+impl RegistryRules {
+    /// Reads rules from `path`, falling back to the defaults when no path is
+    /// given and [`DEFAULT_RULES_FILE`] does not exist.
+    pub fn load(path: Option<&Path>) -> Result<RegistryRules, RulesError> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_RULES_FILE).exists() => Path::new(DEFAULT_RULES_FILE),
+            None => return Ok(RegistryRules::default()),
+        };
+        let content = std::fs::read_to_string(path).map_err(|e| RulesError {
+            kind: RulesErrorKind::IOError(e),
+        })?;
+        serde_json::from_str(&content).map_err(|e| RulesError {
+            kind: RulesErrorKind::Serde(e),
+        })
+    }
+
+    /// Returns the first rule excluding the package, checking the deny list first.
+    pub fn check(&self, name: &str, versions: &[MooncakeInfo]) -> Option<Exclusion> {
+        if let Some(reason) = self.deny.get(name) {
+            return Some(Exclusion::Denied(reason.clone()));
+        }
+        let owner = name.split('/').next().unwrap_or_default();
+        if self.deny_owners.iter().any(|o| o == owner) {
+            return Some(Exclusion::DeniedOwner(owner.to_string()));
+        }
+        if !self.allow_owners.is_empty() && !self.allow_owners.iter().any(|o| o == owner) {
+            return Some(Exclusion::OwnerNotAllowed);
+        }
+        if let Some(pattern) = self.exclude_names.iter().find(|p| glob_match(p, name)) {
+            return Some(Exclusion::ExcludedName(pattern.clone()));
+        }
+        if !self.include_names.is_empty() && !self.include_names.iter().any(|p| glob_match(p, name))
+        {
+            return Some(Exclusion::NameNotIncluded);
+        }
+        let keywords = |info: &MooncakeInfo| info.keywords.clone().unwrap_or_default();
+        for info in versions {
+            if let Some(keyword) = keywords(info)
+                .into_iter()
+                .find(|k| self.exclude_keywords.contains(k))
+            {
+                return Some(Exclusion::ExcludedKeyword(keyword));
+            }
+        }
+        if !self.include_keywords.is_empty() {
+            let latest = versions.last().map(keywords).unwrap_or_default();
+            if !latest.iter().any(|k| self.include_keywords.contains(k)) {
+                return Some(Exclusion::MissingKeyword);
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn rules_report_the_excluding_rule() {
+    let info = |keywords: &[&str]| -> MooncakeInfo {
+        serde_json::from_value(serde_json::json!({
+            "version": "0.1.0",
+            "keywords": keywords,
+        }))
+        .unwrap()
+    };
+    let mut rules = RegistryRules::default();
+    rules.deny.insert(
+        "a/fixture".to_string(),
+        "intentionally broken test fixture".to_string(),
+    );
+    rules.deny_owners.push("spam".to_string());
+    rules.exclude_names.push("*/example?".to_string());
+
+    assert_eq!(rules.check("a/lib", &[info(&[])]), None);
+    assert_eq!(
+        rules.check("a/fixture", &[info(&[])]),
+        Some(Exclusion::Denied(
+            "intentionally broken test fixture".to_string()
+        ))
+    );
+    assert_eq!(
+        rules.check("spam/lib", &[info(&[])]),
+        Some(Exclusion::DeniedOwner("spam".to_string()))
+    );
+    assert_eq!(
+        rules.check("a/example1", &[info(&[])]),
+        Some(Exclusion::ExcludedName("*/example?".to_string()))
+    );
+    assert_eq!(rules.check("a/example12", &[info(&[])]), None);
+    assert_eq!(
+        rules.check("a/t", &[info(&["mooncakes-test"]), info(&[])]),
+        Some(Exclusion::ExcludedKeyword("mooncakes-test".to_string()))
+    );
+
+    rules.include_keywords.push("json".to_string());
+    assert_eq!(
+        rules.check("a/lib", &[info(&[])]),
+        Some(Exclusion::MissingKeyword)
+    );
+    assert_eq!(rules.check("a/lib", &[info(&["json"])]), None);
+}
@@ -0,0 +1,174 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Host directories mounted read-only so the toolchain and system tools work.
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc", "/opt"];
+
+/// Output of a command that tried to reach the network.
+const NETWORK_PATTERNS: &[&str] = &[
+    "Network is unreachable",
+    "Temporary failure in name resolution",
+    "Could not resolve host",
+];
+
+/// Runs builds through bubblewrap: the system and the toolchain are read-only,
+/// only the workdir is writable, the home directory is empty and the
+/// environment holds nothing but what `moon` needs.
+#[derive(Debug, Clone, Default)]
+pub struct Sandbox {
+    /// Also unshare the network namespace, leaving only loopback.
+    pub deny_network: bool,
+}
+
+impl Sandbox {
+    /// Wraps `program args` to run in `workdir` with the toolchain at `moon_home`.
+    pub fn command(
+        &self,
+        program: &Path,
+        args: &[&str],
+        workdir: &Path,
+        moon_home: &Path,
+        path: &std::ffi::OsStr,
+    ) -> Command {
+        let home = home::home_dir().unwrap_or_else(|| PathBuf::from("/root"));
+        let mut cmd = Command::new("bwrap");
+        for dir in SYSTEM_DIRS {
+            cmd.arg("--ro-bind-try").arg(dir).arg(dir);
+        }
+        cmd.args(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"])
+            .arg("--tmpfs")
+            .arg(&home)
+            .arg("--ro-bind")
+            .arg(moon_home)
+            .arg(moon_home);
+        // dependencies are downloaded through the registry cache, which starts
+        // empty; without network they are prefetched and the cache stays read-only
+        let cache = moon_home.join("registry").join("cache");
+        if cache.is_dir() && !self.deny_network {
+            cmd.arg("--tmpfs").arg(&cache);
+        }
+        cmd.arg("--bind")
+            .arg(workdir)
+            .arg(workdir)
+            .arg("--chdir")
+            .arg(workdir)
+            .args(["--unshare-all", "--die-with-parent", "--new-session"]);
+        if !self.deny_network {
+            cmd.arg("--share-net");
+        }
+        cmd.arg("--clearenv")
+            .arg("--setenv")
+            .arg("HOME")
+            .arg(&home)
+            .arg("--setenv")
+            .arg("MOON_HOME")
+            .arg(moon_home)
+            .arg("--setenv")
+            .arg("PATH")
+            .arg(path)
+            .arg("--")
+            .arg(program)
+            .args(args);
+        cmd
+    }
+
+    /// The first line of a failing command's output, run in `workdir`, that
+    /// points at the sandbox: an error from bubblewrap itself, a write outside
+    /// the workdir, or a network access when the network is denied.
+    pub fn find_violation(&self, output: &str, workdir: &Path) -> Option<String> {
+        let workdir = workdir.to_string_lossy();
+        output
+            .lines()
+            .find(|line| {
+                line.contains("bwrap:")
+                    || line.contains("Read-only file system") && !line.contains(&*workdir)
+                    || self.deny_network && NETWORK_PATTERNS.iter().any(|p| line.contains(p))
+            })
+            .map(|line| line.trim().to_string())
+    }
+}
+
+/// Checks that bubblewrap is installed and allowed to create namespaces here.
+pub fn check_available() -> Result<(), std::io::Error> {
+    let output = Command::new("bwrap")
+        .args(["--ro-bind", "/", "/", "--unshare-all", "true"])
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn sandbox_command_and_violations() {
+    let sandbox = Sandbox { deny_network: true };
+    let cmd = sandbox.command(
+        Path::new("/opt/moon/bin/moon"),
+        &["build", "--target", "js"],
+        Path::new("/tmp/build/0.1.0"),
+        Path::new("/opt/moon"),
+        std::ffi::OsStr::new("/opt/moon/bin:/usr/bin"),
+    );
+    let args: Vec<String> = cmd
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    let joined = args.join(" ");
+    assert!(joined.contains("--ro-bind /opt/moon /opt/moon"));
+    assert!(joined.contains("--bind /tmp/build/0.1.0 /tmp/build/0.1.0"));
+    assert!(!args.contains(&"--share-net".to_string()));
+    assert!(joined.ends_with("-- /opt/moon/bin/moon build --target js"));
+    // the workdir is mounted after /tmp is emptied, or it would be hidden
+    let position = |arg: &str| joined.find(arg).unwrap();
+    assert!(position("--tmpfs /tmp") < position("--bind /tmp/build"));
+
+    // the registry cache is emptied, unless dependencies were prefetched for a build without network
+    let home = tempfile::tempdir().unwrap();
+    let cache = home.path().join("registry").join("cache");
+    std::fs::create_dir_all(&cache).unwrap();
+    let mounts_cache = |sandbox: &Sandbox| {
+        let cmd = sandbox.command(
+            Path::new("moon"),
+            &[],
+            Path::new("/tmp/build"),
+            home.path(),
+            std::ffi::OsStr::new("/usr/bin"),
+        );
+        cmd.get_args().any(|a| a == cache.as_os_str())
+    };
+    assert!(mounts_cache(&Sandbox::default()));
+    assert!(!mounts_cache(&sandbox));
+
+    let workdir = Path::new("/tmp/build/0.1.0");
+    assert_eq!(
+        sandbox.find_violation(
+            "Compiling\nerror: failed to write /opt/moon/lib/x: Read-only file system (os error 30)\n",
+            workdir
+        ),
+        Some("error: failed to write /opt/moon/lib/x: Read-only file system (os error 30)".to_string())
+    );
+    assert_eq!(
+        sandbox.find_violation("error: Could not resolve host: mooncakes.io", workdir),
+        Some("error: Could not resolve host: mooncakes.io".to_string())
+    );
+    let shared = Sandbox {
+        deny_network: false,
+    };
+    assert_eq!(
+        shared.find_violation("error: Could not resolve host: mooncakes.io", workdir),
+        None
+    );
+    // the package's own errors are build failures
+    assert_eq!(
+        sandbox.find_violation("Error: [4014] type mismatch", workdir),
+        None
+    );
+    assert_eq!(
+        sandbox.find_violation("error: ./run.sh: Permission denied", workdir),
+        None
+    );
+}
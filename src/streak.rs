@@ -0,0 +1,270 @@
+use std::{collections::HashMap, fmt::Write};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::dashboard::{MoonBuildDashboard, MoonCommand, Status, ToolChainLabel};
+
+pub const DEFAULT_STREAKS_FILE: &str = "data/streaks.json";
+
+/// The most recent run in which a failing cell still passed.
+#[derive(Debug, Serialize)]
+pub struct LastPass {
+    pub run_id: String,
+    pub run_number: String,
+    pub start_time: String,
+    /// The source version that passed, which may predate the failing one.
+    pub version: String,
+    pub moon_version: String,
+    pub moonc_version: String,
+    pub revision: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureStreak {
+    pub source: String,
+    pub version: String,
+    pub toolchain: ToolChainLabel,
+    pub cell: String,
+    /// Number of consecutive recorded runs the cell has failed in.
+    pub streak: usize,
+    pub first_failing_run_id: String,
+    pub first_failing_run_number: String,
+    /// `None` when the cell never passed in the recorded history.
+    pub last_pass: Option<LastPass>,
+}
+
+/// Cells are tracked across source versions, so a new release that still
+/// fails extends the streak.
+type CellKey = (String, ToolChainLabel, MoonCommand);
+
+struct CellStatus {
+    status: Status,
+    version: String,
+    revision: Option<String>,
+    /// Copied from an earlier run by an incremental run rather than built.
+    carried_over: bool,
+}
+
+/// Status of every cell in `run`. A cell of a source built at several versions
+/// fails when any of them fails.
+fn statuses(run: &MoonBuildDashboard) -> HashMap<CellKey, CellStatus> {
+    let mut cells: HashMap<CellKey, CellStatus> = HashMap::new();
+    for label in ToolChainLabel::all() {
+        for cell in run.cells(label) {
+            let key = (cell.source.full_name(), label, cell.cmd);
+            if cells.get(&key).is_some_and(|c| !c.status.is_success()) {
+                continue;
+            }
+            cells.insert(
+                key,
+                CellStatus {
+                    status: cell.result.status,
+                    version: cell.version.to_string(),
+                    revision: cell.revision.map(|r| r.to_string()),
+                    carried_over: cell.result.carried_over.is_some(),
+                },
+            );
+        }
+    }
+    cells
+}
+
+/// Computes how long every cell failing in the last run has been failing.
+/// Runs without data for a cell, or that carried it over from an earlier run,
+/// neither extend nor break its streak.
+pub fn failure_streaks(runs: &[MoonBuildDashboard]) -> Vec<FailureStreak> {
+    let Some(latest) = runs.last() else {
+        return vec![];
+    };
+    // most recent first
+    let history: Vec<_> = runs.iter().rev().map(|run| (run, statuses(run))).collect();
+
+    let mut streaks = vec![];
+    for (key, latest_cell) in &history[0].1 {
+        if latest_cell.status.is_success() {
+            continue;
+        }
+        let mut streak = 0;
+        let mut first_failing = latest;
+        let mut last_pass = None;
+        for (run, cells) in &history {
+            let Some(cell) = cells.get(key).filter(|c| !c.carried_over) else {
+                continue;
+            };
+            if cell.status.is_success() {
+                let toolchain = run.toolchain_version(key.1);
+                last_pass = Some(LastPass {
+                    run_id: run.run_id.clone(),
+                    run_number: run.run_number.clone(),
+                    start_time: run.start_time.clone(),
+                    version: cell.version.clone(),
+                    moon_version: toolchain.moon_version.clone(),
+                    moonc_version: toolchain.moonc_version.clone(),
+                    revision: cell.revision.clone(),
+                });
+                break;
+            }
+            streak += 1;
+            first_failing = run;
+        }
+        let (source, toolchain, cmd) = key.clone();
+        streaks.push(FailureStreak {
+            source,
+            version: latest_cell.version.clone(),
+            toolchain,
+            cell: cmd.to_string(),
+            streak,
+            first_failing_run_id: first_failing.run_id.clone(),
+            first_failing_run_number: first_failing.run_number.clone(),
+            last_pass,
+        });
+    }
+    streaks.sort_by(|a, b| {
+        b.streak
+            .cmp(&a.streak)
+            .then_with(|| a.source.cmp(&b.source))
+            .then_with(|| a.version.cmp(&b.version))
+            .then_with(|| a.toolchain.cmp(&b.toolchain))
+            .then_with(|| a.cell.cmp(&b.cell))
+    });
+    streaks
+}
+
+pub fn render_streaks(streaks: &[FailureStreak]) -> String {
+    let mut out = String::new();
+    if streaks.is_empty() {
+        let _ = writeln!(out, "{}", "nothing is failing".green().bold());
+        return out;
+    }
+    for s in streaks {
+        let since = match &s.last_pass {
+            Some(pass) => format!(
+                "last passed in run {} at {} with {} / {}{}",
+                pass.run_number,
+                pass.version,
+                pass.moon_version,
+                pass.moonc_version,
+                pass.revision
+                    .as_ref()
+                    .map(|r| format!(" at {}", r))
+                    .unwrap_or_default()
+            ),
+            None => "never passed".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "{} {} {} {} {}: broken since run {}, {}",
+            format!("{:>3} runs", s.streak).red().bold(),
+            s.source,
+            s.version,
+            s.toolchain,
+            s.cell,
+            s.first_failing_run_number,
+            since
+        );
+    }
+    out
+}
+
+/// A run of `a/a` at `version` with `build:js` on bleeding at `status`, or
+/// without a/a on bleeding when `status` is `None`.
+#[cfg(test)]
+fn bleeding_run(run_id: &str, version: &str, status: Option<Status>) -> MoonBuildDashboard {
+    use crate::dashboard::{test_build, test_dashboard, MooncakeSource};
+
+    let mut run = test_dashboard(run_id, &["a/a"]);
+    if let MooncakeSource::MooncakesIO { version: v, .. } = &mut run.sources[0] {
+        *v = vec![version.to_string()];
+    }
+    run.stable_release_data = vec![test_build(0, version, Status::Success)];
+    if let Some(status) = status {
+        let mut state = test_build(0, version, Status::Success);
+        state.cbts[0].as_mut().unwrap().build.js.status = status;
+        run.bleeding_release_data = vec![state];
+    }
+    run
+}
+
+#[test]
+fn streak_counts_consecutive_failures() {
+    let (pass, fail) = (Some(Status::Success), Some(Status::Failure));
+    // run 2 has no data for a/a on bleeding, and a/a 0.2.0 still fails in the last run
+    let mut runs = vec![
+        bleeding_run("0", "0.1.0", fail),
+        bleeding_run("1", "0.1.0", pass),
+        bleeding_run("2", "0.1.0", None),
+        bleeding_run("3", "0.1.0", fail),
+        bleeding_run("4", "0.2.0", fail),
+    ];
+    // the last run carried the failure over from run 4
+    let mut carried = bleeding_run("5", "0.2.0", fail);
+    carried.bleeding_release_data[0].cbts[0]
+        .as_mut()
+        .unwrap()
+        .build
+        .js
+        .carried_over = Some("4".to_string());
+    runs.push(carried);
+
+    let streaks = failure_streaks(&runs);
+    assert_eq!(streaks.len(), 1);
+    assert_eq!(streaks[0].cell, "build:js");
+    assert_eq!(streaks[0].toolchain, ToolChainLabel::Bleeding);
+    assert_eq!(streaks[0].version, "0.2.0");
+    assert_eq!(streaks[0].streak, 2);
+    assert_eq!(streaks[0].first_failing_run_id, "3");
+    let last_pass = streaks[0].last_pass.as_ref().unwrap();
+    assert_eq!(
+        (last_pass.run_id.as_str(), last_pass.version.as_str()),
+        ("1", "0.1.0")
+    );
+
+    // a cell failing in every recorded run never passed
+    let streaks = failure_streaks(&runs[3..]);
+    assert_eq!(streaks[0].streak, 2);
+    assert!(streaks[0].last_pass.is_none());
+    assert!(failure_streaks(&runs[1..2]).is_empty());
+}
+
+#[test]
+fn any_failing_version_fails_the_cell() {
+    use crate::dashboard::MooncakeSource;
+
+    // a/a 0.1.0 passes and 0.2.0 fails in the same run
+    let mut run = bleeding_run("0", "0.1.0", Some(Status::Success));
+    if let MooncakeSource::MooncakesIO { version, .. } = &mut run.sources[0] {
+        version.push("0.2.0".to_string());
+    }
+    let mut failing = run.bleeding_release_data[0].cbts[0].clone();
+    failing.as_mut().unwrap().build.js.status = Status::Failure;
+    run.bleeding_release_data[0].cbts.push(failing);
+    run.bleeding_release_data[0]
+        .revisions
+        .push(Some("0.2.0".to_string()));
+
+    let streaks = failure_streaks(&[run]);
+    assert_eq!(streaks.len(), 1);
+    assert_eq!(
+        (streaks[0].version.as_str(), streaks[0].streak),
+        ("0.2.0", 1)
+    );
+}
+
+#[test]
+fn registries_are_told_apart() {
+    use crate::dashboard::{test_build, test_dashboard, MooncakeSource};
+
+    // a/lib fails on the acme registry only
+    let mut run = test_dashboard("1", &["a/lib", "a/lib"]);
+    if let MooncakeSource::MooncakesIO { registry, .. } = &mut run.sources[1] {
+        *registry = Some("acme".to_string());
+    }
+    let mut failing = test_build(1, "0.1.0", Status::Success);
+    failing.cbts[0].as_mut().unwrap().test.js.status = Status::Failure;
+    run.stable_release_data = vec![test_build(0, "0.1.0", Status::Success), failing];
+
+    let streaks = failure_streaks(&[run]);
+    assert_eq!(streaks.len(), 1);
+    assert_eq!(streaks[0].source, "acme:a/lib");
+}
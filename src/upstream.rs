@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+use crate::dashboard::{MoonBuildDashboard, MoonCommand, Status, ToolChainLabel};
+
+/// One cell of a registry source, for its latest published version and its upstream HEAD.
+#[derive(Debug, Serialize)]
+pub struct UpstreamCell {
+    pub toolchain: ToolChainLabel,
+    pub source: String,
+    pub version: String,
+    pub repository: String,
+    pub revision: String,
+    pub cmd: MoonCommand,
+    pub published: Status,
+    pub upstream: Status,
+}
+
+impl UpstreamCell {
+    /// The published version fails where upstream passes, so a fix awaits publishing.
+    pub fn fixed_upstream(&self) -> bool {
+        !self.published.is_success() && self.upstream.is_success()
+    }
+}
+
+/// Pairs every cell of the last published version of each registry source
+/// with the same cell of its upstream HEAD, when that was built.
+pub fn upstream_cells(run: &MoonBuildDashboard) -> Vec<UpstreamCell> {
+    let mut cells = vec![];
+    for label in ToolChainLabel::all() {
+        for state in run.release_data(label) {
+            let (Some(source), Some(upstream)) = (run.sources.get(state.source), &state.upstream)
+            else {
+                continue;
+            };
+            let (Some(published), Some(upstream_cbt)) =
+                (state.cbts.last().and_then(|c| c.as_ref()), &upstream.cbt)
+            else {
+                continue;
+            };
+            for cmd in MoonCommand::all() {
+                cells.push(UpstreamCell {
+                    toolchain: label,
+                    source: source.name().to_string(),
+                    version: source.versions().last().cloned().unwrap_or_default(),
+                    repository: upstream.repository.clone(),
+                    revision: upstream.revision.clone().unwrap_or_default(),
+                    cmd,
+                    published: published.get(cmd).status,
+                    upstream: upstream_cbt.get(cmd).status,
+                });
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn upstream_cells_pair_published_and_head() {
+    use crate::dashboard::{test_build, test_dashboard, Backend, UpstreamBuild};
+
+    // only a/lib on stable was built upstream, where build:js passes
+    let mut run = test_dashboard("1", &["a/lib", "b/lib"]);
+    let mut upstream = test_build(0, "abc1234", Status::Failure).cbts[0]
+        .take()
+        .unwrap();
+    upstream.build.js.status = Status::Success;
+    let mut compared = test_build(0, "0.1.0", Status::Failure);
+    compared.upstream = Some(UpstreamBuild {
+        repository: "https://github.com/a/lib".to_string(),
+        revision: Some("abc1234".to_string()),
+        cbt: Some(upstream),
+    });
+    run.stable_release_data = vec![compared, test_build(1, "0.1.0", Status::Failure)];
+    run.bleeding_release_data = vec![test_build(0, "0.1.0", Status::Failure)];
+
+    let cells = upstream_cells(&run);
+    assert_eq!(cells.len(), MoonCommand::all().len());
+    assert!(cells
+        .iter()
+        .all(|c| c.toolchain == ToolChainLabel::Stable && c.source == "a/lib"));
+    let fixed: Vec<&UpstreamCell> = cells.iter().filter(|c| c.fixed_upstream()).collect();
+    assert_eq!(fixed.len(), 1);
+    assert_eq!(fixed[0].cmd, MoonCommand::Build(Backend::Js));
+    assert_eq!(fixed[0].version, "0.1.0");
+    assert_eq!(fixed[0].revision, "abc1234");
+}
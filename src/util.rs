@@ -1,4 +1,4 @@
-use std::{io::Write, string::FromUtf8Error};
+use std::{io::Write, path::Path, string::FromUtf8Error};
 
 #[derive(Debug, thiserror::Error)]
 #[error("moon operations error: {cmd}")]
@@ -18,48 +18,43 @@ pub enum MoonOpsErrorKind {
     FromUtf8Error(#[from] FromUtf8Error),
 }
 
-pub fn get_moon_version() -> Result<String, MoonOpsError> {
-    let cmd = "moon version";
-    let output = std::process::Command::new("moon")
-        .args(["version"])
+fn get_version(program: &Path, args: &[&str]) -> Result<String, MoonOpsError> {
+    let cmd = format!("{} {}", program.display(), args.join(" "));
+    let output = std::process::Command::new(program)
+        .args(args)
         .output()
         .map_err(|e| MoonOpsError {
-            cmd: cmd.to_string(),
+            cmd: cmd.clone(),
             kind: MoonOpsErrorKind::IOError(e),
         })?;
     if !output.status.success() {
         return Err(MoonOpsError {
-            cmd: cmd.to_string(),
+            cmd,
             kind: MoonOpsErrorKind::ReturnNonZero(output.status),
         });
     }
     let version = String::from_utf8(output.stdout).map_err(|e| MoonOpsError {
-        cmd: cmd.to_string(),
+        cmd,
         kind: MoonOpsErrorKind::FromUtf8Error(e),
     })?;
     Ok(version.trim().to_string())
 }
 
+pub fn get_moon_version() -> Result<String, MoonOpsError> {
+    get_version(Path::new("moon"), &["version"])
+}
+
 pub fn get_moonc_version() -> Result<String, MoonOpsError> {
-    let cmd = "moonc -v";
-    let output = std::process::Command::new("moonc")
-        .args(["-v"])
-        .output()
-        .map_err(|e| MoonOpsError {
-            cmd: cmd.to_string(),
-            kind: MoonOpsErrorKind::IOError(e),
-        })?;
-    if !output.status.success() {
-        return Err(MoonOpsError {
-            cmd: cmd.to_string(),
-            kind: MoonOpsErrorKind::ReturnNonZero(output.status),
-        });
-    }
-    let version = String::from_utf8(output.stdout).map_err(|e| MoonOpsError {
-        cmd: cmd.to_string(),
-        kind: MoonOpsErrorKind::FromUtf8Error(e),
-    })?;
-    Ok(version.trim().to_string())
+    get_version(Path::new("moonc"), &["-v"])
+}
+
+/// Returns the `moon` and `moonc` versions of a toolchain installed at `home`,
+/// a directory laid out like `~/.moon`.
+pub fn get_toolchain_version_at(home: &Path) -> Result<(String, String), MoonOpsError> {
+    let bin = home.join("bin");
+    let moon_version = get_version(&bin.join("moon"), &["version"])?;
+    let moonc_version = get_version(&bin.join("moonc"), &["-v"])?;
+    Ok((moon_version, moonc_version))
 }
 
 fn install_release(args: &[&str]) -> Result<(), MoonOpsError> {
@@ -1 +0,0 @@
-/// <reference types="vite/client" />